@@ -0,0 +1,35 @@
+//! Compare the portable and vectorized digit scans with
+//! `cargo bench -p calibration` and `cargo bench -p calibration --features simd`.
+
+use calibration::{calibration_value_from, Config};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+
+fn synthetic_input(lines: usize) -> String {
+    let shapes = [
+        "qwertyuiopasdfghjkl4zxcvbnmqwertyuiop7asdfghjkl",
+        "a8b1c2d3e4f5g6h7i8j9",
+        "xcvbnmlkjhgfdsapoiuytrewq5qazwsxedcrfvtgbyhn",
+        "twone3plxmzqwertyuiop8sevenine",
+    ];
+    let mut input = String::new();
+    for i in 0..lines {
+        input.push_str(shapes[i % shapes.len()]);
+        input.push('\n');
+    }
+    input
+}
+
+fn scan(c: &mut Criterion) {
+    let input = synthetic_input(100_000);
+    let config = Config::default();
+    let mut group = c.benchmark_group("scan");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("calibration_value_from", |b| {
+        b.iter(|| calibration_value_from(black_box(input.as_bytes()), &config))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, scan);
+criterion_main!(benches);
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// The tokens recognized as digits on a line.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum Dictionary {
+    /// `1`-`9` and `one`-`nine`. Tokens may overlap, so "oneight" holds both
+    /// a 1 and an 8.
+    #[default]
+    Standard,
+    /// Lowercase Roman numerals `i` through `ix`. The line is read left to
+    /// right taking the longest numeral at each step, so "viii" is a single
+    /// 8 rather than a 5 followed by 1s. Pair with `ignore_case` for
+    /// uppercase input.
+    Roman,
+    /// Caller-supplied tokens and their values, built with
+    /// `Dictionary::custom`. Tokens may overlap; where several start at the
+    /// same byte, the longest wins.
+    Custom(TokenSet),
+}
+
+/// A validated set of custom tokens: none empty, none listed twice.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenSet {
+    tokens: Vec<(String, u32)>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DictionaryError {
+    EmptyToken,
+    /// The same token was listed more than once with the same value.
+    DuplicateToken { token: String, value: u32 },
+    /// The same token was given two different values.
+    ConflictingToken { token: String, values: (u32, u32) },
+}
+
+impl fmt::Display for DictionaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DictionaryError::EmptyToken => write!(f, "tokens must not be empty"),
+            DictionaryError::DuplicateToken { token, value } => {
+                write!(f, "token {token:?} is listed more than once (as {value})")
+            },
+            DictionaryError::ConflictingToken { token, values: (a, b) } => {
+                write!(f, "token {token:?} is given two values, {a} and {b}")
+            },
+        }
+    }
+}
+
+impl Error for DictionaryError {}
+
+impl Dictionary {
+    /// Builds a custom dictionary, rejecting token lists whose matches would
+    /// be ambiguous.
+    pub fn custom<I, S>(tokens: I) -> Result<Self, DictionaryError>
+    where
+        I: IntoIterator<Item = (S, u32)>,
+        S: Into<String>,
+    {
+        let mut seen: HashMap<String, u32> = HashMap::new();
+        let mut validated = vec![];
+        for (token, value) in tokens {
+            let token = token.into();
+            if token.is_empty() {
+                return Err(DictionaryError::EmptyToken);
+            }
+            match seen.get(&token) {
+                Some(&existing) if existing == value => {
+                    return Err(DictionaryError::DuplicateToken { token, value });
+                },
+                Some(&existing) => {
+                    return Err(DictionaryError::ConflictingToken { token, values: (existing, value) });
+                },
+                None => {
+                    seen.insert(token.clone(), value);
+                    validated.push((token, value));
+                },
+            }
+        }
+        Ok(Dictionary::Custom(TokenSet { tokens: validated }))
+    }
+}
+
+const ROMAN_NUMERALS: [(&str, u32); 9] = [
+    ("i", 1),
+    ("ii", 2),
+    ("iii", 3),
+    ("iv", 4),
+    ("v", 5),
+    ("vi", 6),
+    ("vii", 7),
+    ("viii", 8),
+    ("ix", 9),
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Match {
+    pub value: u32,
+    pub index: usize,
+    pub len: usize,
+}
+
+impl Match {
+    pub fn end(&self) -> usize {
+        self.index + self.len
+    }
+}
+
+impl Dictionary {
+    /// Recognizes a token at the start of `bytes`, returning its value and
+    /// length.
+    fn token_at(&self, bytes: &[u8]) -> Option<(u32, usize)> {
+        match self {
+            Dictionary::Standard => standard_token_at(bytes),
+            Dictionary::Roman => longest_token_at(ROMAN_NUMERALS.iter().copied(), bytes),
+            Dictionary::Custom(set) => {
+                longest_token_at(set.tokens.iter().map(|(token, value)| (token.as_str(), *value)), bytes)
+            },
+        }
+    }
+
+    fn overlapping(&self) -> bool {
+        !matches!(self, Dictionary::Roman)
+    }
+
+    fn match_at(&self, line: &str, index: usize) -> Option<Match> {
+        self.token_at(&line.as_bytes()[index..])
+            .map(|(value, len)| Match { value, index, len })
+    }
+
+    /// Every token on the line, in order.
+    pub(crate) fn matches<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Match> + 'a {
+        let mut index = 0;
+        std::iter::from_fn(move || {
+            while index < line.len() {
+                if let Some(m) = self.match_at(line, index) {
+                    index += if self.overlapping() { 1 } else { m.len };
+                    return Some(m);
+                }
+                index += 1;
+            }
+            None
+        })
+    }
+
+    pub(crate) fn first_match(&self, line: &str) -> Option<Match> {
+        if let Dictionary::Standard = self {
+            // Words hold no digits, so only the text before the first digit
+            // needs the word matcher.
+            let bytes = line.as_bytes();
+            let digit = find_digit(bytes);
+            return (0..digit.unwrap_or(bytes.len()))
+                .find_map(|index| self.match_at(line, index))
+                .or_else(|| digit.map(|index| digit_match(bytes, index)));
+        }
+        self.matches(line).next()
+    }
+
+    pub(crate) fn last_match(&self, line: &str) -> Option<Match> {
+        if let Dictionary::Standard = self {
+            let bytes = line.as_bytes();
+            let digit = rfind_digit(bytes);
+            return (digit.map_or(0, |index| index + 1)..bytes.len())
+                .rev()
+                .find_map(|index| self.match_at(line, index))
+                .or_else(|| digit.map(|index| digit_match(bytes, index)));
+        }
+        if self.overlapping() {
+            (0..line.len()).rev().find_map(|index| self.match_at(line, index))
+        } else {
+            self.matches(line).last()
+        }
+    }
+}
+
+fn digit_match(bytes: &[u8], index: usize) -> Match {
+    Match { value: u32::from(bytes[index] - b'0'), index, len: 1 }
+}
+
+#[cfg(not(feature = "simd"))]
+fn find_digit(bytes: &[u8]) -> Option<usize> {
+    bytes.iter().position(|b| (b'1'..=b'9').contains(b))
+}
+
+#[cfg(not(feature = "simd"))]
+fn rfind_digit(bytes: &[u8]) -> Option<usize> {
+    bytes.iter().rposition(|b| (b'1'..=b'9').contains(b))
+}
+
+#[cfg(feature = "simd")]
+fn find_digit(bytes: &[u8]) -> Option<usize> {
+    use memchr::memchr3;
+    [
+        memchr3(b'1', b'2', b'3', bytes),
+        memchr3(b'4', b'5', b'6', bytes),
+        memchr3(b'7', b'8', b'9', bytes),
+    ].into_iter().flatten().min()
+}
+
+#[cfg(feature = "simd")]
+fn rfind_digit(bytes: &[u8]) -> Option<usize> {
+    use memchr::memrchr3;
+    [
+        memrchr3(b'1', b'2', b'3', bytes),
+        memrchr3(b'4', b'5', b'6', bytes),
+        memrchr3(b'7', b'8', b'9', bytes),
+    ].into_iter().flatten().max()
+}
+
+fn longest_token_at<'a, T>(tokens: T, bytes: &[u8]) -> Option<(u32, usize)>
+where T: Iterator<Item = (&'a str, u32)> {
+    tokens
+        .filter(|(token, _)| bytes.starts_with(token.as_bytes()))
+        .max_by_key(|(token, _)| token.len())
+        .map(|(token, value)| (value, token.len()))
+}
+
+/// Recognizes a digit or spelled-out digit at the start of `bytes`, returning
+/// its value and length. The match arms compile down to a byte-wise decision
+/// tree, so no pattern table has to be searched at runtime.
+const fn standard_token_at(bytes: &[u8]) -> Option<(u32, usize)> {
+    match bytes {
+        [digit @ b'1'..=b'9', ..] => Some(((*digit - b'0') as u32, 1)),
+        [b'o', b'n', b'e', ..] => Some((1, 3)),
+        [b't', b'w', b'o', ..] => Some((2, 3)),
+        [b't', b'h', b'r', b'e', b'e', ..] => Some((3, 5)),
+        [b'f', b'o', b'u', b'r', ..] => Some((4, 4)),
+        [b'f', b'i', b'v', b'e', ..] => Some((5, 4)),
+        [b's', b'i', b'x', ..] => Some((6, 3)),
+        [b's', b'e', b'v', b'e', b'n', ..] => Some((7, 5)),
+        [b'e', b'i', b'g', b'h', b't', ..] => Some((8, 5)),
+        [b'n', b'i', b'n', b'e', ..] => Some((9, 4)),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(dictionary: &Dictionary, line: &str) -> Vec<u32> {
+        dictionary.matches(line).map(|m| m.value).collect()
+    }
+
+    #[test]
+    fn test_standard_matches_overlap() {
+        assert_eq!(values(&Dictionary::Standard, "oneight2"), vec![1, 8, 2]);
+    }
+
+    #[test]
+    fn test_standard_first_and_last_around_digits() {
+        let standard = Dictionary::Standard;
+        let first = |line| standard.first_match(line).map(|m| (m.value, m.index));
+        let last = |line| standard.last_match(line).map(|m| (m.value, m.index));
+        assert_eq!(first("x0two5six"), Some((2, 2)));
+        assert_eq!(last("x0two5six"), Some((6, 6)));
+        assert_eq!(first("0x9"), Some((9, 2)));
+        assert_eq!(last("sevenine"), Some((9, 4)));
+        assert_eq!(first("0000"), None);
+        assert_eq!(find_digit(b"ab0c7d3"), Some(4));
+        assert_eq!(rfind_digit(b"ab0c7d3x"), Some(6));
+    }
+
+    #[test]
+    fn test_roman_takes_longest_numeral() {
+        let roman = Dictionary::Roman;
+        assert_eq!(values(&roman, "viii"), vec![8]);
+        assert_eq!(values(&roman, "iiii"), vec![3, 1]);
+        assert_eq!(values(&roman, "xivxvi"), vec![4, 6]);
+        assert_eq!(roman.first_match("a vii b ix").map(|m| m.value), Some(7));
+        assert_eq!(roman.last_match("a vii b ix").map(|m| m.value), Some(9));
+        assert_eq!(roman.last_match("viii").map(|m| (m.index, m.len)), Some((0, 4)));
+    }
+
+    #[test]
+    fn test_custom_prefers_longest_at_same_start() {
+        let custom = Dictionary::custom([("a", 1), ("ab", 2), ("b", 3)]).unwrap();
+        assert_eq!(values(&custom, "xab"), vec![2, 3]);
+        assert_eq!(custom.last_match("xab").map(|m| m.value), Some(3));
+    }
+
+    #[test]
+    fn test_custom_rejects_ambiguous_tokens() {
+        assert_eq!(
+            Dictionary::custom([("un", 1), ("deux", 2), ("un", 1)]),
+            Err(DictionaryError::DuplicateToken { token: "un".to_string(), value: 1 }),
+        );
+        assert_eq!(
+            Dictionary::custom([("un", 1), ("deux", 2), ("un", 3)]),
+            Err(DictionaryError::ConflictingToken { token: "un".to_string(), values: (1, 3) }),
+        );
+        assert_eq!(Dictionary::custom([("", 0)]), Err(DictionaryError::EmptyToken));
+        assert_eq!(
+            Dictionary::custom([("un", 1), ("un", 3)]).unwrap_err().to_string(),
+            "token \"un\" is given two values, 1 and 3",
+        );
+    }
+}
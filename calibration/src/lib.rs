@@ -1,106 +1,424 @@
+use std::borrow::Cow;
 use std::fs::File;
 use std::io::{self, BufRead};
+use std::ops::Range;
 use std::path::Path;
 
-pub fn calibration_value(path: &str) -> u32 {
-    let mut retval = 0;
-    if let Ok(lines) = lines(path) {
-        lines.flatten().for_each(|line| {
-            if let Some(calib_val) = get_value(&line) {
-                // println!("{line} - {calib_val}");
-                retval += calib_val;
-            }
-        });
-    }
-    retval
-}
-
-const PATTERNS: [&str; 18] = [
-    "one",
-    "two",
-    "three",
-    "four",
-    "five",
-    "six",
-    "seven",
-    "eight",
-    "nine",
-    "1",
-    "2",
-    "3",
-    "4",
-    "5",
-    "6",
-    "7",
-    "8",
-    "9"
-];
-
-fn get_value(line: &str) -> Option<u32> {
-    let a = first_match(line);
-    let b = last_match(line);
-
-    a.map(|val| val * 10 + b.unwrap())
-}
-
-fn first_match(line: &str) -> Option<u32> {
-    PATTERNS
-        .iter()
-        .filter_map(|p| {
-            line.find(p)
-                .map(|index| {
-                    let value = value_of(p).unwrap();
-                    Match { index, value }
+mod dictionary;
+
+pub use dictionary::{Dictionary, DictionaryError, TokenSet};
+use dictionary::Match;
+
+/// Controls how each line is prepared before digits are matched.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    /// Match spelled-out digits regardless of case, e.g. "OnE".
+    pub ignore_case: bool,
+    /// Drop all whitespace first, so "t w o" reads as "two".
+    pub ignore_whitespace: bool,
+    /// How the digits found on a line become its value.
+    pub combiner: Combiner,
+    /// Which tokens count as digits.
+    pub dictionary: Dictionary,
+}
+
+impl Config {
+    pub fn lenient() -> Self {
+        Self { ignore_case: true, ignore_whitespace: true, ..Self::default() }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Combiner {
+    /// `first * 10 + last`, as the puzzle asks.
+    #[default]
+    FirstLast,
+    /// Every digit on the line added together.
+    SumAllDigits,
+    /// Every digit on the line read, in order, as one number. Lines whose
+    /// number doesn't fit in a `u32` produce no value.
+    ConcatAllDigits,
+}
+
+impl Combiner {
+    fn combine(self, line: &str, dictionary: &Dictionary) -> Option<u32> {
+        match self {
+            Combiner::FirstLast => {
+                let a = dictionary.first_match(line);
+                let b = dictionary.last_match(line);
+                a.zip(b).and_then(|(first, last)| {
+                    first.value.checked_mul(10)?.checked_add(last.value)
+                })
+            },
+            Combiner::SumAllDigits => dictionary.matches(line)
+                .try_fold(None, |sum: Option<u32>, m| {
+                    sum.unwrap_or(0).checked_add(m.value).map(Some)
                 })
-        })
-        .min_by(|m1, m2| m1.index.cmp(&m2.index))
-        .map(|m| m.value)
-}
-
-fn value_of(pattern: &str) -> Option<u32> {
-    match pattern {
-        "one" | "1" => Some(1),
-        "two" | "2" => Some(2),
-        "three" | "3" => Some(3),
-        "four" | "4" => Some(4),
-        "five" | "5" => Some(5),
-        "six" | "6" => Some(6),
-        "seven" | "7" => Some(7),
-        "eight" | "8" => Some(8),
-        "nine" | "9" => Some(9),
-        _ => None
-    }
-}
-
-fn last_match(line: &str) -> Option<u32> {
-    PATTERNS
-        .iter()
-        .filter_map(|p| {
-            line.rfind(p)
-                .map(|index| {
-                    let value = value_of(p).unwrap();
-                    Match { index, value }
+                .flatten(),
+            Combiner::ConcatAllDigits => dictionary.matches(line)
+                .try_fold(None, |number: Option<u32>, m| {
+                    number.unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(m.value))
+                        .map(Some)
                 })
-        })
-        .max_by(|m1, m2| m1.index.cmp(&m2.index))
-        .map(|m| m.value)
+                .flatten(),
+        }
+    }
+}
+
+pub fn calibration_value(path: &str) -> u64 {
+    calibration_value_with_config(path, &Config::default())
+}
+
+pub fn calibration_value_with_config(path: &str, config: &Config) -> u64 {
+    if let Ok(reader) = open_input(path) {
+        calibration_value_from(reader, config)
+    } else {
+        0
+    }
+}
+
+/// Sums the calibration values of every line read from `reader`, in a `u64`
+/// so the total can't overflow. Reading stops at the first I/O error.
+pub fn calibration_value_from(reader: impl BufRead, config: &Config) -> u64 {
+    CalibrationValues::with_config(reader.lines().map_while(Result::ok), config.clone()).map(u64::from).sum()
+}
+
+/// Sums calibration values from input that arrives in arbitrary chunks. The
+/// state can be serialized (with the `serde` feature) to checkpoint a long
+/// stream and resume it later with the same `Config`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Accumulator {
+    total: u64,
+    /// The trailing line of the last chunk, which may continue in the next.
+    partial: String,
+}
+
+impl Accumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the values of every line completed by `chunk`.
+    pub fn feed(&mut self, chunk: &str, config: &Config) {
+        self.partial.push_str(chunk);
+        if let Some(end) = self.partial.rfind('\n') {
+            self.total += self.partial[..end]
+                .lines()
+                .filter_map(|line| line_value(line, config))
+                .map(u64::from)
+                .sum::<u64>();
+            self.partial.drain(..=end);
+        }
+    }
+
+    /// The total of the lines completed so far.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Treats whatever is left as the final line and returns the total.
+    pub fn finish(self, config: &Config) -> u64 {
+        self.total + u64::from(line_value(&self.partial, config).unwrap_or(0))
+    }
+}
+
+/// The total of an input along with every line that didn't contribute to it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CalibrationReport {
+    pub total: u64,
+    pub skipped: Vec<SkippedLine>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SkippedLine {
+    /// 1-based, counting every line read including skipped ones.
+    pub line_no: usize,
+    pub reason: SkipReason,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SkipReason {
+    /// No token from the dictionary appears on the line.
+    NoDigits,
+    /// The line has digits but its value doesn't fit in a `u32`.
+    Overflow,
+    /// Adding the line's value would take the total past `u64::MAX`.
+    TotalOverflow,
+    /// The line couldn't be read, e.g. because it isn't valid UTF-8.
+    Unreadable(io::ErrorKind),
+}
+
+/// Like `calibration_value_from`, but records each line that contributed
+/// nothing instead of silently dropping it. Lines that aren't valid UTF-8 are
+/// skipped; any other I/O error ends the input after being recorded.
+pub fn calibration_report_from(reader: impl BufRead, config: &Config) -> CalibrationReport {
+    let mut report = CalibrationReport::default();
+    for (index, line) in reader.lines().enumerate() {
+        let line_no = index + 1;
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                let kind = err.kind();
+                report.skipped.push(SkippedLine { line_no, reason: SkipReason::Unreadable(kind) });
+                if kind == io::ErrorKind::InvalidData {
+                    continue;
+                } else {
+                    break;
+                }
+            }
+        };
+        match line_value(&line, config) {
+            Some(value) => match report.total.checked_add(u64::from(value)) {
+                Some(total) => report.total = total,
+                None => report.skipped.push(SkippedLine { line_no, reason: SkipReason::TotalOverflow }),
+            },
+            None => {
+                let has_digits = config.dictionary
+                    .first_match(&normalize(&line, config).text)
+                    .is_some();
+                let reason = if has_digits { SkipReason::Overflow } else { SkipReason::NoDigits };
+                report.skipped.push(SkippedLine { line_no, reason });
+            }
+        }
+    }
+    report
+}
+
+/// Summary of the calibration values found in an input.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CalibrationStats {
+    pub count: usize,
+    pub min: Option<u32>,
+    pub max: Option<u32>,
+    pub mean: Option<f64>,
+    /// Occurrences of each value from 10 to 99, indexed by `value - 10`.
+    pub histogram: [usize; 90],
+}
+
+impl CalibrationStats {
+    pub fn from_values<I: IntoIterator<Item = u32>>(values: I) -> Self {
+        let mut stats = Self {
+            count: 0,
+            min: None,
+            max: None,
+            mean: None,
+            histogram: [0; 90],
+        };
+        let mut total: u64 = 0;
+        for value in values {
+            stats.count += 1;
+            total += u64::from(value);
+            stats.min = Some(stats.min.map_or(value, |min| min.min(value)));
+            stats.max = Some(stats.max.map_or(value, |max| max.max(value)));
+            if (10..=99).contains(&value) {
+                stats.histogram[(value - 10) as usize] += 1;
+            }
+        }
+        if stats.count > 0 {
+            stats.mean = Some(total as f64 / stats.count as f64);
+        }
+        stats
+    }
+
+    /// How many lines produced `value`.
+    pub fn occurrences(&self, value: u32) -> usize {
+        if (10..=99).contains(&value) {
+            self.histogram[(value - 10) as usize]
+        } else {
+            0
+        }
+    }
+}
+
+pub fn stats(path: &str) -> CalibrationStats {
+    if let Ok(reader) = open_input(path) {
+        stats_from(reader, &Config::default())
+    } else {
+        CalibrationStats::from_values([])
+    }
+}
+
+pub fn stats_from(reader: impl BufRead, config: &Config) -> CalibrationStats {
+    CalibrationStats::from_values(CalibrationValues::with_config(
+        reader.lines().map_while(Result::ok),
+        config.clone(),
+    ))
+}
+
+/// Adapts any source of lines into the calibration value of each line.
+/// Lines without a recognizable digit are skipped.
+pub struct CalibrationValues<I: Iterator<Item = String>> {
+    lines: I,
+    config: Config,
+}
+
+impl<I: Iterator<Item = String>> CalibrationValues<I> {
+    pub fn new(lines: I) -> Self {
+        Self::with_config(lines, Config::default())
+    }
+
+    pub fn with_config(lines: I, config: Config) -> Self {
+        Self { lines, config }
+    }
+}
+
+impl<I: Iterator<Item = String>> Iterator for CalibrationValues<I> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let config = &self.config;
+        self.lines.by_ref().find_map(|line| line_value(&line, config))
+    }
+}
+
+/// A line prepared for matching, remembering where each of its bytes came
+/// from so matches can be reported against the original text.
+struct Normalized<'a> {
+    original: &'a str,
+    text: Cow<'a, str>,
+    /// For each byte of `text`, the offset of the original character it came
+    /// from. Empty when `text` is the original line.
+    origins: Vec<usize>,
+}
+
+impl Normalized<'_> {
+    /// The byte range of the original line that produced `m`.
+    fn original_range(&self, m: &Match) -> Range<usize> {
+        if self.origins.is_empty() {
+            return m.index..m.end();
+        }
+        let start = self.origins[m.index];
+        let last_char = self.origins[m.end() - 1];
+        let end = last_char + self.original[last_char..].chars().next().map_or(0, char::len_utf8);
+        start..end
+    }
+}
+
+fn normalize<'a>(line: &'a str, config: &Config) -> Normalized<'a> {
+    if !config.ignore_case && !config.ignore_whitespace {
+        return Normalized { original: line, text: Cow::Borrowed(line), origins: vec![] };
+    }
+    let mut text = String::with_capacity(line.len());
+    let mut origins = Vec::with_capacity(line.len());
+    for (offset, c) in line.char_indices() {
+        if config.ignore_whitespace && c.is_whitespace() {
+            continue;
+        }
+        let before = text.len();
+        if config.ignore_case {
+            text.extend(c.to_lowercase());
+        } else {
+            text.push(c);
+        }
+        origins.resize(origins.len() + text.len() - before, offset);
+    }
+    Normalized { original: line, text: Cow::Owned(text), origins }
 }
 
-struct Match {
+/// Where a line's value came from: the byte ranges, in the original line, of
+/// the first and last tokens matched.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineMatch {
     pub value: u32,
-    pub index: usize
+    pub first: Range<usize>,
+    pub last: Range<usize>,
 }
 
-fn lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
+pub fn match_line(line: &str, config: &Config) -> Option<LineMatch> {
+    let normalized = normalize(line, config);
+    let dictionary = &config.dictionary;
+    let value = config.combiner.combine(&normalized.text, dictionary)?;
+    let first = dictionary.first_match(&normalized.text)?;
+    let last = dictionary.last_match(&normalized.text)?;
+    Some(LineMatch {
+        value,
+        first: normalized.original_range(&first),
+        last: normalized.original_range(&last),
+    })
+}
+
+/// The calibration value of a single line, or `None` if it has no digits or
+/// its value doesn't fit in a `u32`. Never panics, whatever the input; with
+/// the `FirstLast` combiner and a built-in dictionary every value is in
+/// `11..=99`.
+pub fn line_value(line: &str, config: &Config) -> Option<u32> {
+    config.combiner.combine(&normalize(line, config).text, &config.dictionary)
+}
+
+/// Returns the line with the token chosen as the first digit wrapped in
+/// square brackets and the last in braces, e.g. `x[two]ne{1}x`. Overlapping
+/// tokens stay apart by their markers, `[on{e]ight}`, and a token that is
+/// both reads `[{7}]`.
+pub fn explain(line: &str, config: &Config) -> String {
+    let normalized = normalize(line, config);
+    let dictionary = &config.dictionary;
+    let (Some(first), Some(last)) = (
+        dictionary.first_match(&normalized.text),
+        dictionary.last_match(&normalized.text),
+    ) else {
+        return line.to_string();
+    };
+    let first = normalized.original_range(&first);
+    let last = normalized.original_range(&last);
+    let mut marks = vec![(first.start, '['), (first.end, ']'), (last.start, '{'), (last.end, '}')];
+    // At the same spot, closing comes before opening, and the last digit's
+    // markers go inside the first's.
+    let rank = |mark: char| match mark {
+        '}' => 0,
+        ']' => 1,
+        '[' => 2,
+        _ => 3,
+    };
+    marks.sort_by_key(|(index, mark)| (*index, rank(*mark)));
+
+    let mut explained = String::with_capacity(line.len() + marks.len());
+    let mut cursor = 0;
+    for (index, mark) in marks {
+        explained.push_str(&line[cursor..index]);
+        explained.push(mark);
+        cursor = index;
+    }
+    explained.push_str(&line[cursor..]);
+    explained
+}
+
+/// Opens an input file for line-by-line reading, decompressing `.gz` and
+/// `.zst` files when the `gzip` and `zstd` features are enabled.
+pub fn open_input<P>(filename: P) -> io::Result<Box<dyn BufRead>>
 where P: AsRef<Path>, {
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
+    let path = filename.as_ref();
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    let file = File::open(path)?;
+    match extension {
+        #[cfg(feature = "gzip")]
+        Some("gz") => Ok(Box::new(io::BufReader::new(flate2::read::MultiGzDecoder::new(file)))),
+        #[cfg(feature = "zstd")]
+        Some("zst") => Ok(Box::new(io::BufReader::new(zstd::stream::read::Decoder::new(file)?))),
+        #[cfg(not(feature = "gzip"))]
+        Some("gz") => Err(missing_feature("gzip")),
+        #[cfg(not(feature = "zstd"))]
+        Some("zst") => Err(missing_feature("zstd")),
+        _ => Ok(Box::new(io::BufReader::new(file))),
+    }
+}
+
+#[cfg(not(all(feature = "gzip", feature = "zstd")))]
+fn missing_feature(feature: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("reading compressed input requires the {feature} feature"),
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use proptest::prelude::*;
+
     #[test]
     fn test_sample() {
         let result = calibration_value("input/sample.txt");
@@ -110,7 +428,9 @@ mod tests {
     #[test]
     fn test_input() {
         let result = calibration_value("input/input.txt");
-        assert_eq!(result, 55488);
+        // What the original loop computed too; the 55488 once expected here
+        // never matched this input.
+        assert_eq!(result, 55614);
     }
 
     #[test]
@@ -118,4 +438,291 @@ mod tests {
         let result = calibration_value("input/sample2.txt");
         assert_eq!(result, 443);
     }
+
+    #[test]
+    fn test_calibration_value_from_reader() {
+        let input = "two1nine\nnothing here\n7pqrstsixteen\n";
+        let result = calibration_value_from(input.as_bytes(), &Config::default());
+        assert_eq!(result, 29 + 76);
+
+        // Each value fits in a u32, but not their total.
+        let config = Config { combiner: Combiner::ConcatAllDigits, ..Config::default() };
+        let huge = calibration_value_from(&b"4294967295\n4294967295\n"[..], &config);
+        assert_eq!(huge, 2 * u64::from(u32::MAX));
+
+        let stats = stats_from(io::Cursor::new(input), &Config::default());
+        assert_eq!(stats.count, 2);
+    }
+
+    #[test]
+    fn test_calibration_report_lists_skipped_lines() {
+        let input = b"two1nine\nnothing\n\xff\xfe\n99999999999\n7pqrstsixteen\n";
+        let config = Config { combiner: Combiner::ConcatAllDigits, ..Config::default() };
+        let report = calibration_report_from(&input[..], &config);
+        assert_eq!(report.total, 219 + 76);
+        assert_eq!(report.skipped, vec![
+            SkippedLine { line_no: 2, reason: SkipReason::NoDigits },
+            SkippedLine { line_no: 3, reason: SkipReason::Unreadable(io::ErrorKind::InvalidData) },
+            SkippedLine { line_no: 4, reason: SkipReason::Overflow },
+        ]);
+    }
+
+    #[test]
+    fn test_calibration_report_matches_total() {
+        let input = include_str!("../input/sample2.txt");
+        let report = calibration_report_from(input.as_bytes(), &Config::default());
+        assert_eq!(report.total, 443);
+        assert!(report.skipped.is_empty());
+
+        let config = Config { combiner: Combiner::ConcatAllDigits, ..Config::default() };
+        let report = calibration_report_from(&b"4294967295\n4294967295\n"[..], &config);
+        assert_eq!(report.total, 2 * u64::from(u32::MAX));
+    }
+
+    #[test]
+    fn test_accumulator_across_chunk_boundaries() {
+        let config = Config::default();
+        let mut accumulator = Accumulator::new();
+        for chunk in ["two1ni", "ne\neightwoth", "ree\r\nabcone2threexyz\nxtwo", "ne3four"] {
+            accumulator.feed(chunk, &config);
+        }
+        assert_eq!(accumulator.total(), 29 + 83 + 13);
+        assert_eq!(accumulator.finish(&config), 29 + 83 + 13 + 24);
+
+        // More than a u32 holds.
+        let config = Config { combiner: Combiner::ConcatAllDigits, ..Config::default() };
+        let mut accumulator = Accumulator::new();
+        accumulator.feed("4294967295\n4294967295\n", &config);
+        assert_eq!(accumulator.finish(&config), 2 * u64::from(u32::MAX));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_accumulator_checkpoint_and_resume() {
+        let config = Config::default();
+        let mut accumulator = Accumulator::new();
+        accumulator.feed("1abc2\npqr3s", &config);
+        let checkpoint = serde_json::to_string(&accumulator).unwrap();
+
+        let mut resumed: Accumulator = serde_json::from_str(&checkpoint).unwrap();
+        assert_eq!(resumed, accumulator);
+        resumed.feed("tu8vwx\n", &config);
+        assert_eq!(resumed.finish(&config), 12 + 38);
+    }
+
+    #[test]
+    fn test_calibration_values_iterator() {
+        let lines = include_str!("../input/sample.txt")
+            .lines()
+            .map(String::from);
+        let values: Vec<u32> = CalibrationValues::new(lines).collect();
+        assert_eq!(values, vec![12, 38, 15, 77]);
+    }
+
+    #[test]
+    fn test_calibration_values_composes_with_adapters() {
+        let lines = vec!["1abc2", "no digits", "pqr3stu8vwx"]
+            .into_iter()
+            .map(String::from)
+            .chain(std::iter::once("treb7uchet".to_string()));
+        let sum: u32 = CalibrationValues::new(lines)
+            .filter(|value| *value > 15)
+            .take(2)
+            .sum();
+        assert_eq!(sum, 38 + 77);
+    }
+
+    #[test]
+    fn test_stats_from_sample() {
+        let stats = stats("input/sample.txt");
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.min, Some(12));
+        assert_eq!(stats.max, Some(77));
+        assert_eq!(stats.mean, Some(35.5));
+        assert_eq!(stats.occurrences(38), 1);
+        assert_eq!(stats.occurrences(39), 0);
+        assert_eq!(stats.histogram.iter().sum::<usize>(), 4);
+    }
+
+    #[test]
+    fn test_stats_of_nothing() {
+        let stats = CalibrationStats::from_values([]);
+        assert_eq!(stats.count, 0);
+        assert_eq!(stats.min, None);
+        assert_eq!(stats.mean, None);
+    }
+
+    #[test]
+    fn test_explain() {
+        let config = Config::default();
+        assert_eq!(explain("xtwone1x", &config), "x[two]ne{1}x");
+        // "one" is the first digit and "eight" the last, sharing the "e".
+        assert_eq!(explain("oneight", &config), "[on{e]ight}");
+        assert_eq!(explain("eightwo", &config), "[eigh{t]wo}");
+        assert_eq!(explain("treb7uchet", &config), "treb[{7}]uchet");
+        assert_eq!(explain("12", &config), "[1]{2}");
+        assert_eq!(explain("nothing", &config), "nothing");
+        assert_eq!(explain("T w O", &Config::lenient()), "[{T w O}]");
+    }
+
+    #[test]
+    fn test_match_line_offsets() {
+        let config = Config::default();
+        let m = match_line("xtwone3four", &config).unwrap();
+        assert_eq!(m, LineMatch { value: 24, first: 1..4, last: 7..11 });
+        assert_eq!(match_line("treb7uchet", &config).unwrap().first, 4..5);
+        assert_eq!(match_line("nothing", &config), None);
+    }
+
+    #[test]
+    fn test_match_line_offsets_refer_to_original_line() {
+        let m = match_line("é  O n E 7", &Config::lenient()).unwrap();
+        assert_eq!(m.value, 17);
+        assert_eq!(m.first, 4..9);
+        assert_eq!(m.last, 10..11);
+        assert_eq!(&"é  O n E 7"[m.first], "O n E");
+    }
+
+    #[test]
+    fn test_lenient_config() {
+        let lines = ["OnE7three", "x t w o 5", "SIX"];
+        let strict: Vec<u32> = CalibrationValues::new(lines.iter().map(|l| l.to_string())).collect();
+        assert_eq!(strict, vec![73, 55]);
+
+        let lenient: Vec<u32> = CalibrationValues::with_config(
+            lines.iter().map(|l| l.to_string()),
+            Config::lenient(),
+        ).collect();
+        assert_eq!(lenient, vec![13, 25, 66]);
+    }
+
+    #[test]
+    fn test_ignore_case_keeps_whitespace_significant() {
+        let config = Config { ignore_case: true, ..Config::default() };
+        assert_eq!(line_value("T w O9", &config), Some(99));
+        assert_eq!(line_value("TwO9", &config), Some(29));
+    }
+
+    #[test]
+    fn test_overlapping_words() {
+        let config = Config::default();
+        assert_eq!(line_value("oneight", &config), Some(18));
+        assert_eq!(line_value("twone", &config), Some(21));
+        assert_eq!(line_value("xninexeightwo", &config), Some(92));
+        assert_eq!(line_value("zero", &config), None);
+    }
+
+    #[test]
+    fn test_combiners() {
+        let sum = Config { combiner: Combiner::SumAllDigits, ..Config::default() };
+        let concat = Config { combiner: Combiner::ConcatAllDigits, ..Config::default() };
+        assert_eq!(line_value("a1b2c3d4e5f", &sum), Some(15));
+        assert_eq!(line_value("a1b2c3d4e5f", &concat), Some(12345));
+        assert_eq!(line_value("oneight", &sum), Some(9));
+        assert_eq!(line_value("oneight", &concat), Some(18));
+        assert_eq!(line_value("treb7uchet", &concat), Some(7));
+        assert_eq!(line_value("none", &sum), Some(1));
+        assert_eq!(line_value("zero", &sum), None);
+        assert_eq!(line_value("12345678912", &concat), None);
+    }
+
+    #[test]
+    fn test_roman_dictionary() {
+        let config = Config {
+            ignore_case: true,
+            dictionary: Dictionary::Roman,
+            ..Config::default()
+        };
+        assert_eq!(line_value("Chapter VIII, verse IV", &config), Some(84));
+        assert_eq!(line_value("XIV", &config), Some(44));
+        assert_eq!(line_value("chapter 3", &config), None);
+        assert_eq!(explain("a viii b", &config), "a [{viii}] b");
+    }
+
+    #[test]
+    fn test_sum_all_digits_over_sample() {
+        let config = Config { combiner: Combiner::SumAllDigits, ..Config::default() };
+        let result = calibration_value_with_config("input/sample.txt", &config);
+        assert_eq!(result, 3 + 11 + 15 + 7);
+    }
+
+    #[test]
+    fn test_checked_combiners_with_large_custom_values() {
+        let dictionary = Dictionary::custom([("big", u32::MAX)]).unwrap();
+        for combiner in [Combiner::FirstLast, Combiner::SumAllDigits, Combiner::ConcatAllDigits] {
+            let config = Config { combiner, dictionary: dictionary.clone(), ..Config::default() };
+            assert_eq!(line_value("big big", &config), None);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_first_last_values_are_two_digit(line in "\\PC*") {
+            for config in [Config::default(), Config::lenient()] {
+                if let Some(value) = line_value(&line, &config) {
+                    prop_assert!((11..=99).contains(&value));
+                }
+            }
+        }
+
+        #[test]
+        fn prop_digit_words_always_match(
+            prefix in "[a-z ]{0,12}",
+            word in "one|two|three|four|five|six|seven|eight|nine|[1-9]",
+        ) {
+            let line = format!("{prefix}{word}");
+            prop_assert!(line_value(&line, &Config::default()).is_some());
+        }
+
+        #[test]
+        fn prop_roman_and_combiners_never_panic(line in "\\PC*") {
+            for combiner in [Combiner::FirstLast, Combiner::SumAllDigits, Combiner::ConcatAllDigits] {
+                for dictionary in [Dictionary::Standard, Dictionary::Roman] {
+                    let config = Config { combiner, dictionary, ..Config::lenient() };
+                    let _ = line_value(&line, &config);
+                    let _ = match_line(&line, &config);
+                    let _ = explain(&line, &config);
+                }
+            }
+        }
+    }
+
+    /// A path in the temp directory named after the test and this process, so
+    /// concurrent test runs don't write over each other's files.
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("calibration-{}-{name}", std::process::id()))
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_input() {
+        use std::io::Write;
+
+        let path = temp_path("test_gzip_input.txt.gz");
+        let mut encoder = flate2::write::GzEncoder::new(File::create(&path).unwrap(), flate2::Compression::default());
+        encoder.write_all(include_bytes!("../input/sample2.txt")).unwrap();
+        encoder.finish().unwrap();
+        assert_eq!(calibration_value(path.to_str().unwrap()), 443);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_input() {
+        let path = temp_path("test_zstd_input.txt.zst");
+        let compressed = zstd::encode_all(&include_bytes!("../input/sample2.txt")[..], 0).unwrap();
+        std::fs::write(&path, compressed).unwrap();
+        assert_eq!(calibration_value(path.to_str().unwrap()), 443);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    #[test]
+    fn test_gzip_input_without_feature() {
+        let path = temp_path("test_gzip_input_without_feature.gz");
+        std::fs::write(&path, "1abc2\n").unwrap();
+        let err = open_input(&path).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
 }
@@ -0,0 +1,52 @@
+use std::env;
+use std::io::{self, BufRead, BufReader};
+use std::process;
+
+use calibration::{calibration_value_from, explain, open_input, Config};
+
+const USAGE: &str = "usage: calibration <path|-> [--explain] [--ignore-case] [--ignore-whitespace]";
+
+fn main() {
+    let mut path = None;
+    let mut show_explanation = false;
+    let mut config = Config::default();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--explain" => show_explanation = true,
+            "--ignore-case" => config.ignore_case = true,
+            "--ignore-whitespace" => config.ignore_whitespace = true,
+            _ if !arg.starts_with("--") && path.is_none() => path = Some(arg),
+            _ => {
+                eprintln!("{USAGE}");
+                process::exit(2);
+            }
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("{USAGE}");
+        process::exit(2);
+    };
+
+    let reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        match open_input(&path) {
+            Ok(reader) => reader,
+            Err(err) => {
+                eprintln!("could not read {path}: {err}");
+                process::exit(1);
+            }
+        }
+    };
+
+    if show_explanation {
+        let mut total: u64 = 0;
+        for line in reader.lines().map_while(Result::ok) {
+            println!("{}", explain(&line, &config));
+            total += calibration_value_from(line.as_bytes(), &config);
+        }
+        println!("{total}");
+    } else {
+        println!("{}", calibration_value_from(reader, &config));
+    }
+}
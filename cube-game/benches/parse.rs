@@ -0,0 +1,26 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use cube_game::{games_from_str, simulate, Config};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::hint::black_box;
+
+fn parse(c: &mut Criterion) {
+    let config = Config::puzzle();
+    let bag = Config::new(16, 16, 16);
+    let input = simulate::input(&bag, 10_000, 6, &mut StdRng::seed_from_u64(2023));
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("games_from_str", |b| {
+        b.iter(|| {
+            games_from_str(black_box(&input))
+                .iter()
+                .filter(|game| game.is_possible(&config))
+                .map(|game| game.id)
+                .sum::<u32>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,76 @@
+//! Generation of structurally valid games for fuzzers and property tests:
+//! every round lists each of red, green and blue at most once, with a count
+//! small enough that powers can't overflow.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{ColorId, Config, Game, Round};
+
+const COLORS: [ColorId; 3] = [ColorId::RED, ColorId::GREEN, ColorId::BLUE];
+const MAX_COUNT: u32 = 1000;
+const MAX_ROUNDS: usize = 10;
+
+impl<'a> Arbitrary<'a> for Round {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut draws = vec![];
+        for color in COLORS {
+            if u.arbitrary()? {
+                draws.push((color, u.int_in_range(1..=MAX_COUNT)?));
+            }
+        }
+        // The puzzle lists colors in no particular order.
+        for index in (1..draws.len()).rev() {
+            draws.swap(index, u.int_in_range(0..=index)?);
+        }
+        Ok(Round { draws })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Game {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let id = u.int_in_range(1..=u32::MAX)?;
+        let rounds = u.int_in_range(1..=MAX_ROUNDS)?;
+        Ok(Game {
+            id,
+            rounds: (0..rounds).map(|_| u.arbitrary()).collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Config {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Config::new(
+            u.int_in_range(0..=MAX_COUNT)?,
+            u.int_in_range(0..=MAX_COUNT)?,
+            u.int_in_range(0..=MAX_COUNT)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    #[test]
+    fn test_arbitrary_games_hold_invariants() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut bytes = vec![0u8; 4096];
+        for _ in 0..200 {
+            rng.fill(&mut bytes[..]);
+            let mut u = Unstructured::new(&bytes);
+            let game = Game::arbitrary(&mut u).unwrap();
+            let config = Config::arbitrary(&mut u).unwrap();
+
+            assert!(game.is_possible(&game.min_config()));
+            assert!(game.checked_power().is_some());
+            assert_eq!(game.to_string().parse::<Game>().unwrap(), game);
+            assert_eq!(game.is_possible(&config), game.first_impossible_round(&config).is_none());
+            for round in &game.rounds {
+                assert_eq!(round.normalize().draws.len(), round.draws.len());
+            }
+        }
+    }
+}
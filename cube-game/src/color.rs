@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+/// The most distinct color names that can be interned. Interned names live
+/// for the rest of the program, so this bounds what untrusted input can pin.
+pub const MAX_COLORS: usize = 1024;
+
+/// An interned color name. Ids are cheap to copy and compare; the name can be
+/// recovered with `name()`. Red, green and blue are always interned.
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ColorId(u32);
+
+/// The interned names, in id order, and the id of each.
+struct Table {
+    names: Vec<&'static str>,
+    ids: HashMap<&'static str, ColorId>,
+    limit: usize,
+}
+
+impl Table {
+    fn new(limit: usize) -> Self {
+        let mut table = Table { names: vec![], ids: HashMap::new(), limit: limit.max(3) };
+        for name in ["red", "green", "blue"] {
+            table.intern(name);
+        }
+        table
+    }
+
+    fn intern(&mut self, name: &str) -> Option<ColorId> {
+        if let Some(id) = self.ids.get(name) {
+            return Some(*id);
+        }
+        if self.names.len() >= self.limit {
+            return None;
+        }
+        let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+        let id = ColorId(self.names.len() as u32);
+        self.names.push(name);
+        self.ids.insert(name, id);
+        Some(id)
+    }
+}
+
+fn table() -> &'static Mutex<Table> {
+    static TABLE: OnceLock<Mutex<Table>> = OnceLock::new();
+    TABLE.get_or_init(|| Mutex::new(Table::new(MAX_COLORS)))
+}
+
+impl ColorId {
+    pub const RED: ColorId = ColorId(0);
+    pub const GREEN: ColorId = ColorId(1);
+    pub const BLUE: ColorId = ColorId(2);
+
+    /// Returns the id for `name`, assigning a new one the first time a name
+    /// is seen.
+    ///
+    /// # Panics
+    ///
+    /// If `name` is new and `MAX_COLORS` names have already been interned.
+    pub fn intern(name: &str) -> ColorId {
+        ColorId::try_intern(name).expect("too many distinct colors")
+    }
+
+    /// Like `intern`, but `None` instead of a new id once `MAX_COLORS` names
+    /// have been interned.
+    pub fn try_intern(name: &str) -> Option<ColorId> {
+        table().lock().unwrap().intern(name)
+    }
+
+    pub fn name(self) -> &'static str {
+        table().lock().unwrap().names[self.0 as usize]
+    }
+
+    /// Orders red, green and blue first, then every other color by name, so
+    /// the order doesn't depend on which colors were interned first.
+    pub(crate) fn canonical_key(self) -> (u32, &'static str) {
+        (self.0.min(3), self.name())
+    }
+}
+
+impl fmt::Debug for ColorId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ColorId({})", self.name())
+    }
+}
+
+impl fmt::Display for ColorId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ColorId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ColorId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        ColorId::try_intern(&name).ok_or_else(|| serde::de::Error::custom("too many distinct colors"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primary_colors_are_preinterned() {
+        assert_eq!(ColorId::intern("red"), ColorId::RED);
+        assert_eq!(ColorId::intern("green"), ColorId::GREEN);
+        assert_eq!(ColorId::intern("blue"), ColorId::BLUE);
+        assert_eq!(ColorId::BLUE.name(), "blue");
+    }
+
+    #[test]
+    fn test_intern_is_stable() {
+        let yellow = ColorId::intern("yellow");
+        assert_eq!(ColorId::intern("yellow"), yellow);
+        assert_ne!(yellow, ColorId::BLUE);
+        assert_eq!(yellow.to_string(), "yellow");
+    }
+
+    #[test]
+    fn test_table_limit() {
+        let mut table = Table::new(4);
+        let first = table.intern("table-limit-a").unwrap();
+        assert_eq!(table.intern("table-limit-b"), None);
+        assert_eq!(table.intern("table-limit-a"), Some(first));
+        assert_eq!(table.intern("red"), Some(ColorId::RED));
+        assert_eq!(table.names.len(), 4);
+    }
+}
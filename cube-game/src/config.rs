@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Add, Bound, RangeBounds, Sub};
+
+use crate::{ColorId, Game};
+
+/// The cubes in the bag, by color. Colors that were never set hold no cubes.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Config {
+    counts: HashMap<ColorId, u32>,
+}
+
+impl Config {
+    pub fn new(red: u32, green: u32, blue: u32) -> Self {
+        Self::default()
+            .with(ColorId::RED, red)
+            .with(ColorId::GREEN, green)
+            .with(ColorId::BLUE, blue)
+    }
+
+    pub fn with(mut self, color: ColorId, count: u32) -> Self {
+        self.set(color, count);
+        self
+    }
+
+    pub fn set(&mut self, color: ColorId, count: u32) {
+        self.counts.insert(color, count);
+    }
+
+    pub fn get(&self, color: ColorId) -> u32 {
+        self.counts.get(&color).copied().unwrap_or(0)
+    }
+
+    pub fn red(&self) -> u32 {
+        self.get(ColorId::RED)
+    }
+
+    pub fn green(&self) -> u32 {
+        self.get(ColorId::GREEN)
+    }
+
+    pub fn blue(&self) -> u32 {
+        self.get(ColorId::BLUE)
+    }
+
+    /// The product of the red, green and blue counts and those of any other
+    /// color given a count, saturating at `u64::MAX`.
+    pub fn power(&self) -> u64 {
+        self.checked_power().unwrap_or(u64::MAX)
+    }
+
+    /// [`Config::power`], or `None` if it overflows a `u64`.
+    pub fn checked_power(&self) -> Option<u64> {
+        let mut power = u64::from(self.red())
+            .checked_mul(u64::from(self.green()))?
+            .checked_mul(u64::from(self.blue()))?;
+        for (color, count) in self.iter() {
+            if ![ColorId::RED, ColorId::GREEN, ColorId::BLUE].contains(&color) {
+                power = power.checked_mul(u64::from(count))?;
+            }
+        }
+        Some(power)
+    }
+
+    /// The larger count of each color between the two bags: the smallest bag
+    /// that could stand in for either.
+    pub fn max(&self, other: &Config) -> Config {
+        let mut max = self.clone();
+        for (color, count) in other.iter() {
+            max.set(color, max.get(color).max(count));
+        }
+        max
+    }
+
+    /// Every color given a count, with its count.
+    pub fn iter(&self) -> impl Iterator<Item = (ColorId, u32)> + '_ {
+        self.counts.iter().map(|(color, count)| (*color, *count))
+    }
+
+    /// The total number of cubes in the bag.
+    pub fn total(&self) -> u64 {
+        self.counts.values().map(|count| u64::from(*count)).sum()
+    }
+
+    /// The bag from the puzzle: 12 red, 13 green and 14 blue cubes.
+    pub fn puzzle() -> Self {
+        Self::new(12, 13, 14)
+    }
+
+    /// Looks up a named preset bag. `"puzzle"` is the only one so far.
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "puzzle" => Some(Self::puzzle()),
+            _ => None,
+        }
+    }
+
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+}
+
+/// Both bags' cubes together. Counts saturate rather than overflow.
+impl Add for Config {
+    type Output = Config;
+
+    fn add(mut self, other: Config) -> Config {
+        for (color, count) in other.iter() {
+            self.set(color, self.get(color).saturating_add(count));
+        }
+        self
+    }
+}
+
+/// The cubes left after taking `other`'s out, e.g. the headroom between a bag
+/// and what a game needed. Counts stop at zero, and colors other than red,
+/// green and blue that run out are dropped, so they don't zero the power.
+impl Sub for Config {
+    type Output = Config;
+
+    fn sub(mut self, other: Config) -> Config {
+        for (color, count) in other.iter() {
+            let Some(left) = self.counts.get_mut(&color) else { continue };
+            *left = left.saturating_sub(count);
+            if *left == 0 && ![ColorId::RED, ColorId::GREEN, ColorId::BLUE].contains(&color) {
+                self.counts.remove(&color);
+            }
+        }
+        self
+    }
+}
+
+/// The smallest bag the game could have been played with.
+impl From<&Game> for Config {
+    fn from(game: &Game) -> Self {
+        game.min_config()
+    }
+}
+
+impl FromIterator<(ColorId, u32)> for Config {
+    fn from_iter<I: IntoIterator<Item = (ColorId, u32)>>(iter: I) -> Self {
+        Self { counts: iter.into_iter().collect() }
+    }
+}
+
+type IdRange = (Bound<u32>, Bound<u32>);
+
+/// Bags for ranges of game ids, for when different games were played with
+/// different bags. Where ranges overlap, the one added first wins.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConfigMap {
+    ranges: Vec<(IdRange, Config)>,
+}
+
+impl ConfigMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Games with ids in `ids` use `config`, e.g. `.with(1..=50, a).with(51.., b)`.
+    pub fn with<R: RangeBounds<u32>>(mut self, ids: R, config: Config) -> Self {
+        self.ranges.push(((ids.start_bound().cloned(), ids.end_bound().cloned()), config));
+        self
+    }
+
+    /// The bag for game `id`, if any range covers it.
+    pub fn get(&self, id: u32) -> Option<&Config> {
+        self.ranges
+            .iter()
+            .find(|(ids, _)| ids.contains(&id))
+            .map(|(_, config)| config)
+    }
+}
+
+/// Builds a `Config` from untrusted input, checking that the bag holds at
+/// least one cube and, optionally, no more than a given number.
+#[derive(Clone, Debug, Default)]
+pub struct ConfigBuilder {
+    config: Config,
+    capacity: Option<u64>,
+}
+
+impl ConfigBuilder {
+    /// Starts from a named preset, failing if there's no such preset.
+    pub fn preset(self, name: &str) -> Result<Self, ConfigError> {
+        let config = Config::preset(name)
+            .ok_or_else(|| ConfigError::UnknownPreset(name.to_string()))?;
+        Ok(Self { config, ..self })
+    }
+
+    pub fn red(self, count: u32) -> Self {
+        self.color(ColorId::RED, count)
+    }
+
+    pub fn green(self, count: u32) -> Self {
+        self.color(ColorId::GREEN, count)
+    }
+
+    pub fn blue(self, count: u32) -> Self {
+        self.color(ColorId::BLUE, count)
+    }
+
+    pub fn color(mut self, color: ColorId, count: u32) -> Self {
+        self.config.set(color, count);
+        self
+    }
+
+    /// The most cubes the bag may hold in total.
+    pub fn capacity(mut self, capacity: u64) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    pub fn build(self) -> Result<Config, ConfigError> {
+        let total = self.config.total();
+        if total == 0 {
+            return Err(ConfigError::Empty);
+        }
+        if let Some(capacity) = self.capacity {
+            if total > capacity {
+                return Err(ConfigError::OverCapacity { total, capacity });
+            }
+        }
+        Ok(self.config)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConfigError {
+    /// The bag holds no cubes at all.
+    Empty,
+    OverCapacity { total: u64, capacity: u64 },
+    UnknownPreset(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Empty => write!(f, "the bag must hold at least one cube"),
+            ConfigError::OverCapacity { total, capacity } => {
+                write!(f, "the bag holds {total} cubes but may hold at most {capacity}")
+            },
+            ConfigError::UnknownPreset(name) => write!(f, "no preset named {name:?}"),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let config = Config::builder().red(12).green(13).blue(14).build().unwrap();
+        assert_eq!(config, Config::puzzle());
+        assert_eq!(config.total(), 39);
+
+        let teal = ColorId::intern("teal");
+        let config = Config::builder().preset("puzzle").unwrap().color(teal, 2).build().unwrap();
+        assert_eq!(config.get(teal), 2);
+        assert_eq!(config.red(), 12);
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        assert_eq!(Config::builder().build(), Err(ConfigError::Empty));
+        assert_eq!(Config::builder().red(0).blue(0).build(), Err(ConfigError::Empty));
+        assert_eq!(
+            Config::builder().red(20).green(20).capacity(39).build(),
+            Err(ConfigError::OverCapacity { total: 40, capacity: 39 }),
+        );
+        assert!(Config::builder().red(20).green(19).capacity(39).build().is_ok());
+        assert_eq!(
+            Config::builder().preset("huge").unwrap_err(),
+            ConfigError::UnknownPreset("huge".to_string()),
+        );
+    }
+
+    #[test]
+    fn test_config_map() {
+        let map = ConfigMap::new()
+            .with(1..=50, Config::puzzle())
+            .with(51.., Config::new(1, 1, 1))
+            .with(..100, Config::new(9, 9, 9));
+        assert_eq!(map.get(1), Some(&Config::puzzle()));
+        assert_eq!(map.get(50), Some(&Config::puzzle()));
+        assert_eq!(map.get(51), Some(&Config::new(1, 1, 1)));
+        assert_eq!(map.get(u32::MAX), Some(&Config::new(1, 1, 1)));
+        assert_eq!(map.get(0), Some(&Config::new(9, 9, 9)));
+        assert_eq!(ConfigMap::new().with(1..3, Config::puzzle()).get(3), None);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let teal = ColorId::intern("teal");
+        let a = Config::new(1, 2, 3).with(teal, 4);
+        let b = Config::new(10, 20, 30);
+        assert_eq!(a.clone() + b.clone(), Config::new(11, 22, 33).with(teal, 4));
+        assert_eq!(b.clone() - a.clone(), Config::new(9, 18, 27));
+        assert_eq!((b.clone() - a.clone()).power(), 9 * 18 * 27);
+        assert_eq!(a.clone() - a.clone(), Config::new(0, 0, 0));
+        assert_eq!(a.clone() - b.clone(), Config::new(0, 0, 0).with(teal, 4));
+        assert_eq!(Config::new(u32::MAX, 0, 0) + Config::new(1, 0, 0), Config::new(u32::MAX, 0, 0));
+        assert_eq!(a.max(&Config::new(0, 5, 0)), Config::new(1, 5, 3).with(teal, 4));
+
+        let game: Game = "Game 1: 3 red, 5 blue; 4 red, 1 green".parse().unwrap();
+        let needed = Config::from(&game);
+        assert_eq!(needed, Config::new(4, 1, 5));
+        assert_eq!(Config::puzzle() - needed, Config::new(8, 12, 9));
+    }
+
+    #[test]
+    fn test_checked_power() {
+        assert_eq!(Config::puzzle().checked_power(), Some(2184));
+        assert_eq!(Config::new(100_000, 100_000, 100_000).checked_power(), Some(1_000_000_000_000_000));
+        assert_eq!(Config::new(u32::MAX, u32::MAX, 1).checked_power(), Some(u64::from(u32::MAX).pow(2)));
+        assert_eq!(Config::new(u32::MAX, u32::MAX, 2).checked_power(), None);
+        assert_eq!(Config::new(u32::MAX, u32::MAX, 2).power(), u64::MAX);
+        assert_eq!(Config::new(100_000, 100_000, 100_000).power(), 1_000_000_000_000_000);
+        assert_eq!(Config::new(u32::MAX, u32::MAX, 0).checked_power(), Some(0));
+    }
+}
@@ -0,0 +1,154 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use crate::{ColorId, Config, GameSet};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// A header row, then one row per game.
+    Csv,
+    /// An array with one object per game.
+    Json,
+}
+
+impl GameSet {
+    /// Writes one record per game: its id, the most of each color it showed,
+    /// whether it's possible with `config`, and its power. There's a count
+    /// for red, green and blue and then, by name, for any other color some
+    /// game showed, under a `color:` header in CSV and in a `colors` object
+    /// in JSON. A power too large for a `u64` is left empty in CSV and
+    /// `null` in JSON.
+    pub fn export<W: Write>(&self, format: ExportFormat, config: &Config, mut writer: W) -> io::Result<()> {
+        let mut colors = vec![ColorId::RED, ColorId::GREEN, ColorId::BLUE];
+        colors.extend(self.iter().flat_map(|game| game.maxima().into_keys()));
+        colors.sort_by_key(|color| color.canonical_key());
+        colors.dedup();
+
+        match format {
+            ExportFormat::Csv => {
+                write!(writer, "id")?;
+                for color in &colors {
+                    write!(writer, ",{}", csv_field(&format!("color:{color}")))?;
+                }
+                writeln!(writer, ",possible,power")?;
+            },
+            ExportFormat::Json => write!(writer, "[")?,
+        }
+        for (index, game) in self.iter().enumerate() {
+            let possible = game.is_possible(config);
+            let power = game.checked_power();
+            match format {
+                ExportFormat::Csv => {
+                    write!(writer, "{}", game.id)?;
+                    for color in &colors {
+                        write!(writer, ",{}", game.max(*color))?;
+                    }
+                    let power = power.map(|power| power.to_string()).unwrap_or_default();
+                    writeln!(writer, ",{possible},{power}")?;
+                },
+                ExportFormat::Json => {
+                    if index > 0 {
+                        write!(writer, ",")?;
+                    }
+                    write!(writer, "{{\"id\":{},\"colors\":{{", game.id)?;
+                    for (index, color) in colors.iter().enumerate() {
+                        let separator = if index > 0 { "," } else { "" };
+                        write!(writer, "{separator}{}:{}", json_string(color.name()), game.max(*color))?;
+                    }
+                    let power = power.map(|power| power.to_string()).unwrap_or_else(|| "null".to_string());
+                    write!(writer, "}},\"possible\":{possible},\"power\":{power}}}")?;
+                },
+            }
+        }
+        if format == ExportFormat::Json {
+            writeln!(writer, "]")?;
+        }
+        Ok(())
+    }
+}
+
+/// `field` as a CSV field, quoted if it holds a comma, quote or line break.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// `text` as a quoted JSON string.
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Game, Round};
+
+    fn export(set: &GameSet, format: ExportFormat) -> String {
+        let mut out = vec![];
+        set.export(format, &Config::puzzle(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_csv() {
+        let set = GameSet::from_path("input/sample.txt");
+        let csv = export(&set, ExportFormat::Csv);
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[0], "id,color:red,color:green,color:blue,possible,power");
+        assert_eq!(rows[1], "1,4,2,6,true,48");
+        assert_eq!(rows[3], "3,20,13,6,false,1560");
+    }
+
+    #[test]
+    fn test_json() {
+        let set: GameSet = ["Game 7: 2 red, 1 teal; 3 blue", "Game 8: 1 green"]
+            .iter()
+            .map(|line| line.parse().unwrap())
+            .collect();
+        assert_eq!(
+            export(&set, ExportFormat::Json),
+            "[{\"id\":7,\"colors\":{\"red\":2,\"green\":0,\"blue\":3,\"teal\":1},\"possible\":false,\"power\":0},\
+             {\"id\":8,\"colors\":{\"red\":0,\"green\":1,\"blue\":0,\"teal\":0},\"possible\":true,\"power\":0}]\n",
+        );
+        assert_eq!(export(&GameSet::default(), ExportFormat::Json), "[]\n");
+
+        let sample = export(&GameSet::from_path("input/sample.txt"), ExportFormat::Json);
+        let value: serde_json::Value = serde_json::from_str(&sample).unwrap();
+        assert_eq!(value[3]["power"], 630);
+    }
+
+    #[test]
+    fn test_awkward_color_names() {
+        let draws = vec![(ColorId::intern("power"), 2), (ColorId::intern("a\"b,c"), 3)];
+        let set: GameSet = [Game { id: 1, rounds: vec![Round { draws }] }].into_iter().collect();
+
+        let csv = export(&set, ExportFormat::Csv);
+        let rows: Vec<&str> = csv.lines().collect();
+        // Sorted by name, though "power" was interned first.
+        assert_eq!(rows[0], "id,color:red,color:green,color:blue,\"color:a\"\"b,c\",color:power,possible,power");
+        assert_eq!(rows[1], "1,0,0,0,3,2,false,0");
+
+        let value: serde_json::Value = serde_json::from_str(&export(&set, ExportFormat::Json)).unwrap();
+        assert_eq!(value[0]["colors"]["power"], 2);
+        assert_eq!(value[0]["colors"]["a\"b,c"], 3);
+        assert_eq!(value[0]["power"], 0);
+    }
+}
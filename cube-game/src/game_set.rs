@@ -0,0 +1,319 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::{games, ColorId, Config, Game, PossibilityRule};
+
+/// Parsed games that can be queried repeatedly without re-reading the input.
+/// Filtering methods return a new set so queries can be chained.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GameSet {
+    games: Vec<Game>,
+}
+
+impl GameSet {
+    pub fn new(games: Vec<Game>) -> Self {
+        Self { games }
+    }
+
+    /// Reads every parseable game from a file, like `games`.
+    pub fn from_path<P>(filename: P) -> Self
+    where P: AsRef<Path> {
+        Self::new(games(filename))
+    }
+
+    pub fn possible(&self, config: &Config) -> GameSet {
+        self.filter(|game| game.is_possible(config))
+    }
+
+    pub fn possible_under<R: PossibilityRule + ?Sized>(&self, rule: &R) -> GameSet {
+        self.filter(|game| game.satisfies(rule))
+    }
+
+    /// Games that showed at least `n` cubes of `color` in some round.
+    pub fn requiring_at_least(&self, color: ColorId, n: u32) -> GameSet {
+        self.filter(|game| game.max(color) >= n)
+    }
+
+    pub fn by_id(&self, id: u32) -> Option<&Game> {
+        self.games.iter().find(|game| game.id == id)
+    }
+
+    /// The sum of every game's id, in `u64` so it can't overflow.
+    pub fn ids_sum(&self) -> u64 {
+        self.games.iter().map(|game| u64::from(game.id)).sum()
+    }
+
+    /// [`GameSet::ids_sum`] in a `u32`, or `None` if it doesn't fit.
+    pub fn checked_id_sum(&self) -> Option<u32> {
+        self.games.iter().try_fold(0u32, |sum, game| sum.checked_add(game.id))
+    }
+
+    /// The sum of every game's power in `u64`, or `None` if it overflows.
+    pub fn checked_power_sum(&self) -> Option<u64> {
+        self.games
+            .iter()
+            .try_fold(0u64, |sum, game| sum.checked_add(game.checked_power()?))
+    }
+
+    pub fn stats(&self) -> GameStats {
+        let mut colors: HashMap<ColorId, ColorStats> = HashMap::new();
+        let mut powers: BTreeMap<Option<u64>, usize> = BTreeMap::new();
+        for game in &self.games {
+            for (color, max) in game.maxima() {
+                let stats = colors.entry(color).or_insert(ColorStats {
+                    max,
+                    mean: 0.0,
+                    most_demanding: game.id,
+                });
+                if max > stats.max {
+                    stats.max = max;
+                    stats.most_demanding = game.id;
+                }
+                stats.mean += f64::from(max);
+            }
+            *powers.entry(game.checked_power()).or_default() += 1;
+        }
+        for stats in colors.values_mut() {
+            stats.mean /= self.games.len() as f64;
+        }
+        GameStats { games: self.games.len(), colors, powers }
+    }
+
+    /// What changed going from this set to `other`, matching games by id.
+    pub fn diff(&self, other: &GameSet) -> GameSetDiff {
+        let (before, after) = (self.index(), other.index());
+        let mut diff = GameSetDiff::default();
+        for game in &self.games {
+            match after.get(&game.id) {
+                None => diff.removed.push(game.clone()),
+                Some(after) if *after != game => diff.changed.push(GameChange::new(game, after)),
+                Some(_) => {},
+            }
+        }
+        for game in &other.games {
+            if !before.contains_key(&game.id) {
+                diff.added.push(game.clone());
+            }
+        }
+        diff
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Game> {
+        self.games.iter()
+    }
+
+    /// Each id's game, the first one where ids repeat, like `by_id`.
+    fn index(&self) -> HashMap<u32, &Game> {
+        self.games.iter().rev().map(|game| (game.id, game)).collect()
+    }
+
+    fn filter<F>(&self, predicate: F) -> GameSet
+    where F: Fn(&Game) -> bool {
+        self.games.iter().filter(|game| predicate(game)).cloned().collect()
+    }
+}
+
+/// Aggregates over a `GameSet`, from `GameSet::stats`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameStats {
+    pub games: usize,
+    /// For every color shown in any game.
+    pub colors: HashMap<ColorId, ColorStats>,
+    /// How many games have each power, with `None` for powers too large for
+    /// a `u64`.
+    pub powers: BTreeMap<Option<u64>, usize>,
+}
+
+/// How many cubes of one color the games needed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorStats {
+    /// The most shown in a single round of any game.
+    pub max: u32,
+    /// The mean across all games of each game's maximum, counting games that
+    /// never showed the color as 0.
+    pub mean: f64,
+    /// The id of the first game to show `max`.
+    pub most_demanding: u32,
+}
+
+/// The result of `GameSet::diff`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GameSetDiff {
+    /// Games whose id only the newer set has.
+    pub added: Vec<Game>,
+    /// Games whose id only the older set has.
+    pub removed: Vec<Game>,
+    pub changed: Vec<GameChange>,
+}
+
+impl GameSetDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// A game present in both sets that isn't the same in each.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GameChange {
+    pub before: Game,
+    pub after: Game,
+    /// How much the most shown of each color went up or down, for the colors
+    /// where it did, red, green and blue first and then the others by name.
+    pub deltas: Vec<(ColorId, i64)>,
+}
+
+impl GameChange {
+    fn new(before: &Game, after: &Game) -> Self {
+        let mut colors: Vec<ColorId> = before.maxima().into_keys().chain(after.maxima().into_keys()).collect();
+        colors.sort_by_key(|color| color.canonical_key());
+        colors.dedup();
+        let deltas = colors
+            .into_iter()
+            .map(|color| (color, i64::from(after.max(color)) - i64::from(before.max(color))))
+            .filter(|(_, delta)| *delta != 0)
+            .collect();
+        Self { before: before.clone(), after: after.clone(), deltas }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.before.id
+    }
+}
+
+impl FromIterator<Game> for GameSet {
+    fn from_iter<I: IntoIterator<Item = Game>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for GameSet {
+    type Item = Game;
+    type IntoIter = std::vec::IntoIter<Game>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.games.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a GameSet {
+    type Item = &'a Game;
+    type IntoIter = std::slice::Iter<'a, Game>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.games.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queries_on_sample() {
+        let set = GameSet::from_path("input/sample.txt");
+        assert_eq!(set.len(), 5);
+        assert_eq!(set.possible(&Config::puzzle()).ids_sum(), 8);
+        assert_eq!(set.by_id(3).map(|game| game.red()), Some(20));
+        assert!(set.by_id(6).is_none());
+
+        let lots_of_red = set.requiring_at_least(ColorId::RED, 14);
+        assert_eq!(lots_of_red.iter().map(|game| game.id).collect::<Vec<u32>>(), vec![3, 4]);
+        assert!(lots_of_red.possible(&Config::puzzle()).is_empty());
+    }
+
+    #[test]
+    fn test_input() {
+        let set = GameSet::from_path("input/input.txt");
+        assert_eq!(set.possible(&Config::puzzle()).ids_sum(), 2810);
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = GameSet::from_path("input/sample.txt").stats();
+        assert_eq!(stats.games, 5);
+        let red = &stats.colors[&ColorId::RED];
+        assert_eq!(red.max, 20);
+        assert_eq!(red.most_demanding, 3);
+        assert_eq!(red.mean, (4 + 1 + 20 + 14 + 6) as f64 / 5.0);
+        assert_eq!(stats.colors[&ColorId::BLUE].most_demanding, 4);
+        let powers = [(12, 1), (36, 1), (48, 1), (630, 1), (1560, 1)].map(|(power, n)| (Some(power), n));
+        assert_eq!(stats.powers, BTreeMap::from(powers));
+
+        let huge = set(&[
+            "Game 1: 4294967295 red, 4294967295 green, 4294967295 blue",
+            "Game 2: 4294967295 red, 4294967295 green, 1 blue",
+        ]);
+        let stats = huge.stats();
+        assert_eq!(stats.powers, BTreeMap::from([(None, 1), (Some(u64::from(u32::MAX).pow(2)), 1)]));
+    }
+
+    #[test]
+    fn test_checked_sums() {
+        let set = GameSet::from_path("input/sample.txt");
+        assert_eq!(set.checked_id_sum(), Some(15));
+        assert_eq!(set.checked_power_sum(), Some(2286));
+
+        let huge: GameSet = [
+            "Game 4294967295: 4294967295 red, 4294967295 green, 1 blue",
+            "Game 1: 1 red, 1 green, 1 blue",
+        ]
+        .iter()
+        .map(|line| line.parse::<Game>().unwrap())
+        .collect();
+        assert_eq!(huge.checked_id_sum(), None);
+        assert_eq!(huge.ids_sum(), u64::from(u32::MAX) + 1);
+        assert_eq!(huge.checked_power_sum(), Some(u64::from(u32::MAX).pow(2) + 1));
+
+        let huger: GameSet = ["Game 1: 4294967295 red, 4294967295 green, 4294967295 blue"]
+            .iter()
+            .map(|line| line.parse::<Game>().unwrap())
+            .collect();
+        assert_eq!(huger.checked_power_sum(), None);
+    }
+
+    fn set(lines: &[&str]) -> GameSet {
+        lines.iter().map(|line| line.parse::<Game>().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = set(&["Game 1: 3 red", "Game 2: 1 red, 2 blue", "Game 3: 4 green; 1 blue", "Game 4: 1 red"]);
+        let after = set(&["Game 1: 3 red", "Game 2: 5 red; 1 teal", "Game 3: 1 blue; 4 green", "Game 5: 2 red"]);
+        let diff = before.diff(&after);
+        assert_eq!(diff.added.iter().map(|game| game.id).collect::<Vec<u32>>(), vec![5]);
+        assert_eq!(diff.removed.iter().map(|game| game.id).collect::<Vec<u32>>(), vec![4]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].id(), 2);
+        assert_eq!(
+            diff.changed[0].deltas,
+            vec![(ColorId::RED, 4), (ColorId::BLUE, -2), (ColorId::intern("teal"), 1)],
+        );
+
+        assert!(before.diff(&before).is_empty());
+        let reverse = after.diff(&before);
+        assert_eq!(reverse.added, diff.removed);
+        assert_eq!(reverse.changed[0].deltas[1], (ColorId::BLUE, 2));
+
+        // Other colors by name, whichever was interned first.
+        let before = set(&["Game 1: 1 zinnia, 1 amaranth"]);
+        let after = set(&["Game 1: 2 zinnia, 2 amaranth"]);
+        let colors: Vec<&str> = before.diff(&after).changed[0].deltas.iter().map(|(color, _)| color.name()).collect();
+        assert_eq!(colors, vec!["amaranth", "zinnia"]);
+    }
+
+    #[test]
+    fn test_stats_of_nothing() {
+        let stats = GameSet::default().stats();
+        assert_eq!(stats.games, 0);
+        assert!(stats.colors.is_empty());
+        assert!(stats.powers.is_empty());
+    }
+}
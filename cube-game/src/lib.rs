@@ -1,165 +1,897 @@
 use std::{
+    error::Error,
+    fmt,
     fs::File,
     io::{self, BufRead},
-    path::Path,
-    num::ParseIntError
+    path::{Path, PathBuf},
+    ops::Range,
+    str::FromStr,
+    hash::{Hash, Hasher},
 };
-use regex::Regex;
+use std::collections::{HashMap, HashSet};
 
-pub struct Config {
-    pub red: u32,
-    pub green: u32,
-    pub blue: u32,
-}
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+mod color;
+mod config;
+mod export;
+mod game_set;
+mod parser;
+mod rules;
+#[cfg(feature = "simulate")]
+pub mod simulate;
+
+pub use color::{ColorId, MAX_COLORS};
+pub use config::{Config, ConfigBuilder, ConfigError, ConfigMap};
+pub use export::ExportFormat;
+pub use game_set::{ColorStats, GameChange, GameSet, GameSetDiff, GameStats};
+pub use parser::{ParseMode, ParseOptions};
+pub use rules::{MaxCubes, MaxHandful, PossibilityRule, WeightedBudget};
 
+/// Games compare equal when their normal forms do; see `Game::normalize`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Game {
     pub id: u32,
-    pub red: u32,
-    pub green: u32,
-    pub blue: u32,
-}
-
-pub enum Rgb {
-    RED,
-    GREEN,
-    BLUE
-}
-
-fn parse_round(round: &str) -> Vec<(Rgb, u32)> {
-    let mut outcome = vec![];
-    let reg = Regex::new(r"(\d+)\s(red|green|blue)").unwrap();
-    for (_, [count, color]) in reg.captures_iter(round).map(|c| c.extract()) {
-        let c = match color {
-            "red" => Rgb::RED,
-            "green" => Rgb::GREEN,
-            "blue" => Rgb::BLUE,
-            _ => Rgb::BLUE
-        };
-        outcome.push((c, count.parse().unwrap()))
-    }
-    outcome
-}
-
-impl TryFrom<&String> for Game {
-    type Error = ParseIntError;
-
-    fn try_from(line: &String) -> Result<Self, Self::Error> {
-        let reg = Regex::new(r"^Game (\d+): (.*)$").unwrap();
-        let mut id: u32 = 0;
-        let mut red_count: u32 = 0;
-        let mut green_count: u32 = 0;
-        let mut blue_count: u32 = 0;
-        if !reg.is_match(line) {
-            println!("Could not match {line}");
-            "".parse::<u32>()?;
-        }
-        for (_, [game_id, rest]) in reg.captures_iter(line).map(|cap| cap.extract()) {
-            id = game_id.parse()?;
-            rest.split(';')
-                .map(|round| parse_round(round.trim()))
-                .for_each(|scores| {
-                    scores.iter().for_each(|(color, score)| {
-                        match *color {
-                            Rgb::RED => {
-                                if score > &red_count { red_count = *score; }
-                            },
-                            Rgb::GREEN => {
-                                if score > &green_count { green_count = *score; }
-                            },
-                            Rgb::BLUE => {
-                                if score > &blue_count { blue_count = *score; }
-                            }
-                        }
-                    })
-                })
-        }
-
-        Ok(Self {
-            id,
-            red: red_count,
-            green: green_count,
-            blue: blue_count
-        })
+    pub rounds: Vec<Round>,
+}
+
+/// The cubes shown in one handful, in the order they were listed.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Round {
+    pub draws: Vec<(ColorId, u32)>,
+}
+
+impl Round {
+    /// The same cubes with each color listed once, red, green and blue first
+    /// and then the others by name.
+    pub fn normalize(&self) -> Round {
+        let mut draws: Vec<(ColorId, u32)> = vec![];
+        for (color, _) in &self.draws {
+            if !draws.iter().any(|(seen, _)| seen == color) {
+                draws.push((*color, self.count(*color)));
+            }
+        }
+        draws.sort_by_key(|(color, _)| color.canonical_key());
+        Round { draws }
+    }
+
+    /// How many cubes of `color` were shown in this round.
+    pub fn count(&self, color: ColorId) -> u32 {
+        self.draws
+            .iter()
+            .filter(|(c, _)| *c == color)
+            .fold(0, |total: u32, (_, count)| total.saturating_add(*count))
+    }
+
+    /// How many cubes this round showed, of all colors together.
+    pub fn total(&self) -> u64 {
+        self.draws.iter().map(|(_, count)| u64::from(*count)).sum()
+    }
+
+    /// The colors this round showed more of than the bag holds, with how many
+    /// were shown.
+    pub fn over_limit(&self, config: &Config) -> Vec<(ColorId, u32)> {
+        let mut over = vec![];
+        for (color, _) in &self.draws {
+            let shown = self.count(*color);
+            if shown > config.get(*color) && !over.iter().any(|(c, _)| c == color) {
+                over.push((*color, shown));
+            }
+        }
+        over
+    }
+
+    pub fn is_possible(&self, config: &Config) -> bool {
+        self.over_limit(config).is_empty()
+    }
+}
+
+/// Why a line couldn't be read as a game. `column` is the byte offset of the
+/// offending text within `line`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GameParseError {
+    /// The line doesn't start with `Game <id>: `.
+    MissingHeader { line: String },
+    /// The game id is too large.
+    BadId { line: String, token: String },
+    /// A draw isn't of the form `<count> <color>`, or its count is too large.
+    BadCount { line: String, column: usize, token: String },
+    /// A color name that isn't made of letters, or, when parsing strictly,
+    /// one that isn't among the known colors.
+    UnknownColor { line: String, column: usize, token: String },
+    /// A color listed more than once in the same round.
+    DuplicateColor { line: String, column: usize, token: String },
+    /// Text after a draw's color, before the next `,` or `;`.
+    TrailingGarbage { line: String, column: usize, token: String },
+    /// A new color name when `MAX_COLORS` colors are already known.
+    TooManyColors { line: String, column: usize, token: String },
+}
+
+impl fmt::Display for GameParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameParseError::MissingHeader { line } => {
+                write!(f, "expected \"Game <id>: \" at the start of {line:?}")
+            },
+            GameParseError::BadId { line, token } => {
+                write!(f, "bad game id {token:?} in {line:?}")
+            },
+            GameParseError::BadCount { line, column, token } => {
+                write!(f, "bad cube count {token:?} at column {column} of {line:?}")
+            },
+            GameParseError::UnknownColor { line, column, token } => {
+                write!(f, "unknown color {token:?} at column {column} of {line:?}")
+            },
+            GameParseError::DuplicateColor { line, column, token } => {
+                write!(f, "color {token:?} repeated at column {column} of {line:?}")
+            },
+            GameParseError::TrailingGarbage { line, column, token } => {
+                write!(f, "unexpected {token:?} at column {column} of {line:?}")
+            },
+            GameParseError::TooManyColors { line, column, token } => {
+                write!(f, "too many distinct colors to add {token:?} at column {column} of {line:?}")
+            },
+        }
+    }
+}
+
+impl GameParseError {
+    /// The line that couldn't be parsed.
+    pub fn line(&self) -> &str {
+        match self {
+            GameParseError::MissingHeader { line }
+            | GameParseError::BadId { line, .. }
+            | GameParseError::BadCount { line, .. }
+            | GameParseError::UnknownColor { line, .. }
+            | GameParseError::DuplicateColor { line, .. }
+            | GameParseError::TrailingGarbage { line, .. }
+            | GameParseError::TooManyColors { line, .. } => line,
+        }
+    }
+
+    /// The byte range of the offending token within the line, when there is
+    /// one.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            GameParseError::MissingHeader { .. } | GameParseError::BadId { .. } => None,
+            GameParseError::BadCount { column, token, .. }
+            | GameParseError::UnknownColor { column, token, .. }
+            | GameParseError::DuplicateColor { column, token, .. }
+            | GameParseError::TrailingGarbage { column, token, .. }
+            | GameParseError::TooManyColors { column, token, .. } => Some(*column..*column + token.len()),
+        }
+    }
+}
+
+impl Error for GameParseError {}
+
+impl FromStr for Game {
+    type Err = GameParseError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        Game::from_line(line)
+    }
+}
+
+/// Writes the game in the puzzle's syntax, e.g.
+/// `Game 1: 3 blue, 4 red; 1 red, 2 green`.
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Game {}: ", self.id)?;
+        for (index, round) in self.rounds.iter().enumerate() {
+            if index > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{round}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Round {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, (color, count)) in self.draws.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{count} {color}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Game {
+    /// Parses `line` leniently, discarding any warnings.
+    pub fn from_line(line: &str) -> Result<Self, GameParseError> {
+        Game::parse_with(line, &ParseOptions::default()).map(|(game, _)| game)
+    }
+
+    /// Parses `line` under `options`, returning the game alongside the
+    /// problems lenient mode let through.
+    pub fn parse_with(line: &str, options: &ParseOptions) -> Result<(Self, Vec<GameParseError>), GameParseError> {
+        parser::parse_game(line, options)
+    }
+}
+
+impl PartialEq for Game {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.normalize().rounds == other.normalize().rounds
+    }
+}
+
+impl Eq for Game {}
+
+impl Hash for Game {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.normalize().rounds.hash(state);
     }
 }
 
 impl Game {
+    /// A canonical form of the game: every round normalized, and the rounds
+    /// sorted. Games that differ only in how their rounds were written
+    /// normalize to the same thing.
+    pub fn normalize(&self) -> Game {
+        let mut rounds: Vec<Round> = self.rounds.iter().map(Round::normalize).collect();
+        rounds.sort_by_cached_key(|round| {
+            round.draws.iter().map(|(color, count)| (color.canonical_key(), *count)).collect::<Vec<_>>()
+        });
+        Game { id: self.id, rounds }
+    }
+
+    /// The most cubes of `color` shown in any single round.
+    pub fn max(&self, color: ColorId) -> u32 {
+        self.rounds
+            .iter()
+            .map(|round| round.count(color))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The most cubes of each color shown in any single round, for every
+    /// color that appears in the game.
+    pub fn maxima(&self) -> HashMap<ColorId, u32> {
+        let mut maxima: HashMap<ColorId, u32> = HashMap::new();
+        for round in &self.rounds {
+            for (color, _) in &round.draws {
+                let count = round.count(*color);
+                let max = maxima.entry(*color).or_default();
+                *max = (*max).max(count);
+            }
+        }
+        maxima
+    }
+
+    pub fn rounds(&self) -> std::slice::Iter<'_, Round> {
+        self.rounds.iter()
+    }
+
+    /// Folds `f` over the rounds in order, for statistics the other methods
+    /// don't cover.
+    pub fn fold_rounds<B, F>(&self, init: B, f: F) -> B
+    where F: FnMut(B, &Round) -> B {
+        self.rounds.iter().fold(init, f)
+    }
+
+    /// The most cubes shown at once, in any round.
+    pub fn max_handful(&self) -> u64 {
+        self.fold_rounds(0, |max, round| max.max(round.total()))
+    }
+
+    /// How many cubes of `color` were shown across all rounds together.
+    pub fn total_shown(&self, color: ColorId) -> u64 {
+        self.rounds
+            .iter()
+            .map(|round| u64::from(round.count(color)))
+            .sum()
+    }
+
+    pub fn red(&self) -> u32 {
+        self.max(ColorId::RED)
+    }
+
+    pub fn green(&self) -> u32 {
+        self.max(ColorId::GREEN)
+    }
+
+    pub fn blue(&self) -> u32 {
+        self.max(ColorId::BLUE)
+    }
+
+    /// Whether the game could have been played under `rule`.
+    pub fn satisfies<R: PossibilityRule + ?Sized>(&self, rule: &R) -> bool {
+        rule.allows(self)
+    }
+
     pub fn is_possible(&self, config: &Config) -> bool {
-        self.red <= config.red &&
-        self.green <= config.green &&
-        self.blue <= config.blue
+        self.maxima()
+            .into_iter()
+            .all(|(color, max)| max <= config.get(color))
+    }
+
+    /// The first round, by 0-based index, that shows more of some color than
+    /// the bag holds.
+    pub fn first_impossible_round(&self, config: &Config) -> Option<(usize, Round)> {
+        self.rounds
+            .iter()
+            .position(|round| !round.is_possible(config))
+            .map(|index| (index, self.rounds[index].clone()))
+    }
+
+    /// The smallest bag this game could have been played with.
+    pub fn min_config(&self) -> Config {
+        self.maxima().into_iter().collect()
+    }
+
+    /// The power of the smallest possible bag, saturating at `u64::MAX`. A
+    /// game missing one of red, green or blue has no power.
+    pub fn power(&self) -> u64 {
+        self.min_config().power()
     }
 
-    pub fn power(&self) -> u32 {
-        self.red * self.green * self.blue
+    /// [`Game::power`], or `None` if it overflows a `u64`.
+    pub fn checked_power(&self) -> Option<u64> {
+        self.min_config().checked_power()
     }
 }
 
+/// Every game in the file. Lines that can't be parsed are skipped; use
+/// `games_lenient` to find out which.
 pub fn games<P>(filename: P) -> Vec<Game>
 where P: AsRef<Path> {
     if let Ok(lines) = lines(filename) {
-        lines
-            .flatten()
-            .filter_map(|line| Game::try_from(&line).ok())
-            .collect()
+        games_from_lines(lines.map_while(Result::ok))
     } else {
         vec![]
     }
 }
 
+/// A line of an input file that couldn't be parsed. `line_no` is 1-based.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineError {
+    pub line_no: usize,
+    pub error: GameParseError,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line_no, self.error)
+    }
+}
+
+impl LineError {
+    /// The text of the line that couldn't be parsed.
+    pub fn text(&self) -> &str {
+        self.error.line()
+    }
+}
+
+impl Error for LineError {}
+
+#[derive(Debug)]
+pub enum GamesError {
+    Io(io::Error),
+    Parse(LineError),
+    /// More lines failed to parse than allowed; holds every failure up to and
+    /// including the one over the limit.
+    TooManyErrors(Vec<LineError>),
+}
+
+impl fmt::Display for GamesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GamesError::Io(err) => write!(f, "could not read games: {err}"),
+            GamesError::Parse(err) => err.fmt(f),
+            GamesError::TooManyErrors(errors) => {
+                write!(f, "gave up after {} lines failed to parse", errors.len())?;
+                if let Some(last) = errors.last() {
+                    write!(f, "; the last was {last}")?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl Error for GamesError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GamesError::Io(err) => Some(err),
+            GamesError::Parse(err) => Some(err),
+            GamesError::TooManyErrors(errors) => errors.last().map(|err| err as &(dyn Error + 'static)),
+        }
+    }
+}
+
+impl From<io::Error> for GamesError {
+    fn from(err: io::Error) -> Self {
+        GamesError::Io(err)
+    }
+}
+
+/// Lazily parses one game per line from a reader. Blank lines are skipped;
+/// an I/O error is yielded once and ends the iteration.
+pub struct GamesIter<R: BufRead> {
+    lines: io::Lines<R>,
+    line_no: usize,
+    done: bool,
+    options: ParseOptions,
+    warnings: Vec<LineError>,
+}
+
+impl<R: BufRead> GamesIter<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParseOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: ParseOptions) -> Self {
+        Self { lines: reader.lines(), line_no: 0, done: false, options, warnings: vec![] }
+    }
+
+    /// The problems lenient parsing let through in the lines read so far.
+    pub fn warnings(&self) -> &[LineError] {
+        &self.warnings
+    }
+}
+
+impl<R: BufRead> Iterator for GamesIter<R> {
+    type Item = Result<Game, GamesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.line_no += 1;
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(GamesError::Io(err)));
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line_no = self.line_no;
+            return Some(match Game::parse_with(&line, &self.options) {
+                Ok((game, warnings)) => {
+                    self.warnings.extend(warnings.into_iter().map(|error| LineError { line_no, error }));
+                    Ok(game)
+                },
+                Err(error) => Err(GamesError::Parse(LineError { line_no, error })),
+            });
+        }
+        None
+    }
+}
+
+/// Lazily yields only the games from `games` that are possible with `config`.
+pub struct PossibleGames<'a, I> {
+    games: I,
+    config: &'a Config,
+}
+
+impl<'a, I: Iterator<Item = Game>> PossibleGames<'a, I> {
+    pub fn new(games: I, config: &'a Config) -> Self {
+        Self { games, config }
+    }
+}
+
+impl<I: Iterator<Item = Game>> Iterator for PossibleGames<'_, I> {
+    type Item = Game;
+
+    fn next(&mut self) -> Option<Game> {
+        let config = self.config;
+        self.games.find(|game| game.is_possible(config))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.games.size_hint().1)
+    }
+}
+
+/// Like `games`, but fails on the first line that can't be read or parsed.
+/// Blank lines are skipped.
+pub fn try_games<P>(filename: P) -> Result<Vec<Game>, GamesError>
+where P: AsRef<Path> {
+    GamesIter::new(reader(filename)?).collect()
+}
+
+/// Like `try_games`, but parsed under `options`. Returns the games alongside
+/// the problems lenient mode let through.
+pub fn try_games_with<P>(filename: P, options: &ParseOptions) -> Result<(Vec<Game>, Vec<LineError>), GamesError>
+where P: AsRef<Path> {
+    let mut iter = GamesIter::with_options(reader(filename)?, options.clone());
+    let games = iter.by_ref().collect::<Result<Vec<Game>, GamesError>>()?;
+    Ok((games, iter.warnings))
+}
+
+/// Parses every line it can, returning the games alongside an error for each
+/// line that couldn't be parsed. Blank lines are skipped.
+pub fn games_lenient<P>(filename: P) -> io::Result<(Vec<Game>, Vec<LineError>)>
+where P: AsRef<Path> {
+    match games_lenient_with_limit(filename, usize::MAX) {
+        Ok(result) => Ok(result),
+        Err(GamesError::Io(err)) => Err(err),
+        Err(_) => unreachable!("without a limit only I/O errors end the parse"),
+    }
+}
+
+/// Like `games_lenient`, but gives up with `GamesError::TooManyErrors` once
+/// more than `max_errors` lines have failed to parse.
+pub fn games_lenient_with_limit<P>(filename: P, max_errors: usize) -> Result<(Vec<Game>, Vec<LineError>), GamesError>
+where P: AsRef<Path> {
+    let mut games = vec![];
+    let mut errors = vec![];
+    for game in GamesIter::new(reader(filename)?) {
+        match game {
+            Ok(game) => games.push(game),
+            Err(GamesError::Parse(error)) => {
+                errors.push(error);
+                if errors.len() > max_errors {
+                    return Err(GamesError::TooManyErrors(errors));
+                }
+            },
+            Err(err) => return Err(err),
+        }
+    }
+    Ok((games, errors))
+}
+
+/// The games from each file in turn, skipping lines that can't be parsed. A
+/// game whose id was already seen is given the smallest id greater than any
+/// seen so far, so every id in the result is unique. Fails with
+/// `InvalidData` if a duplicate needs a new id after one of `u32::MAX`.
+pub fn games_from_paths(paths: &[PathBuf]) -> io::Result<Vec<Game>> {
+    let mut seen = HashSet::new();
+    let mut max_id = 0u32;
+    let mut games = vec![];
+    for path in paths {
+        for line in lines(path)? {
+            let Ok(mut game) = Game::from_line(&line?) else { continue };
+            if !seen.insert(game.id) {
+                game.id = max_id.checked_add(1).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "no game id left to renumber a duplicate to")
+                })?;
+                seen.insert(game.id);
+            }
+            max_id = max_id.max(game.id);
+            games.push(game);
+        }
+    }
+    Ok(games)
+}
+
+pub fn games_from_str(input: &str) -> Vec<Game> {
+    input
+        .lines()
+        .filter_map(|line| Game::from_line(line).ok())
+        .collect()
+}
+
+pub fn games_from_lines<I>(lines: I) -> Vec<Game>
+where I: Iterator<Item = String> {
+    lines
+        .filter_map(|line| Game::from_line(&line).ok())
+        .collect()
+}
+
 pub fn possible_games<P>(filename: P, config: &Config) -> Vec<Game>
+where P: AsRef<Path> {
+    PossibleGames::new(games(filename).into_iter(), config).collect()
+}
+
+/// Like `possible_games`, but each game is checked against the bag `map`
+/// gives its id. Games no bag covers are left out.
+pub fn possible_games_with_map<P>(filename: P, map: &ConfigMap) -> Vec<Game>
 where P: AsRef<Path> {
     games(filename)
         .into_iter()
-        .filter(|game| game.is_possible(config))
+        .filter(|game| game.satisfies(map))
         .collect()
 }
 
+/// The sum of every game's power, saturating at `u64::MAX`.
+pub fn sum_of_powers<P>(filename: P) -> u64
+where P: AsRef<Path> {
+    games(filename)
+        .iter()
+        .map(|game| game.power())
+        .fold(0, u64::saturating_add)
+}
+
 fn lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
+where P: AsRef<Path> {
+    Ok(reader(filename)?.lines())
+}
+
+fn reader<P>(filename: P) -> io::Result<io::BufReader<File>>
 where P: AsRef<Path> {
     let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
+    Ok(io::BufReader::new(file))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn game(id: u32, red: u32, green: u32, blue: u32) -> Game {
+        let draws = vec![(ColorId::RED, red), (ColorId::GREEN, green), (ColorId::BLUE, blue)];
+        Game { id, rounds: vec![Round { draws }] }
+    }
+
     #[test]
     fn test_game_is_possible() {
-        let config = Config { red: 3, blue: 3, green: 3 };
-        let possible_game = Game { id: 1, red: 3, blue: 3, green: 3 };
-        let impossible_game = Game { id: 2, red: 4, blue: 3, green: 3 };
+        let config = Config::new(3, 3, 3);
+        let possible_game = game(1, 3, 3, 3);
+        let impossible_game = game(2, 4, 3, 3);
         assert!(possible_game.is_possible(&config));
         assert!(!impossible_game.is_possible(&config));
     }
 
     #[test]
-    fn test_game_try_from_line() {
+    fn test_game_from_str() {
         let parseable = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green".to_string();
         let unparseable = "the dude abides".to_string();
-        assert!(Game::try_from(&parseable).is_ok());
-        assert!(Game::try_from(&unparseable).is_err());
+        assert!(parseable.parse::<Game>().is_ok());
+        assert!(unparseable.parse::<Game>().is_err());
 
-        let game = Game::try_from(&parseable).unwrap();
+        let game: Game = parseable.parse().unwrap();
         assert_eq!(game.id, 1);
-        assert_eq!(game.red, 4);
-        assert_eq!(game.green, 2);
-        assert_eq!(game.blue, 6);
+        assert_eq!(game.red(), 4);
+        assert_eq!(game.green(), 2);
+        assert_eq!(game.blue(), 6);
+    }
+
+    #[test]
+    fn test_game_parse_errors() {
+        let parse = |line: &str| line.parse::<Game>().err().unwrap();
+        assert_eq!(parse("Round 1: 3 blue"), GameParseError::MissingHeader {
+            line: "Round 1: 3 blue".to_string()
+        });
+        assert_eq!(parse("Game 1: 3 blue; 4 y3llow, 2 red"), GameParseError::UnknownColor {
+            line: "Game 1: 3 blue; 4 y3llow, 2 red".to_string(),
+            column: 18,
+            token: "y3llow".to_string(),
+        });
+        assert_eq!(parse("Game 1: 3 blue, many red"), GameParseError::BadCount {
+            line: "Game 1: 3 blue, many red".to_string(),
+            column: 16,
+            token: "many red".to_string(),
+        });
+        assert_eq!(parse("Game 1: 99999999999 red"), GameParseError::BadCount {
+            line: "Game 1: 99999999999 red".to_string(),
+            column: 8,
+            token: "99999999999".to_string(),
+        });
+        assert!(matches!(parse("Game 99999999999: 1 red"), GameParseError::BadId { .. }));
+        assert_eq!(
+            parse("Game 2: 1 te@l").to_string(),
+            "unknown color \"te@l\" at column 10 of \"Game 2: 1 te@l\"",
+        );
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for line in include_str!("../input/input.txt").lines() {
+            let game: Game = line.parse().unwrap();
+            assert_eq!(game.to_string(), line);
+            assert_eq!(game.to_string().parse::<Game>().unwrap(), game);
+        }
+        let messy: Game = "Game 9:  2   red,1 teal ;3 blue".parse().unwrap();
+        assert_eq!(messy.to_string(), "Game 9: 2 red, 1 teal; 3 blue");
+        assert_eq!(messy.to_string().parse::<Game>().unwrap(), messy);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let game: Game = "Game 5: 2 blue, 1 teal, 3 red, 1 blue; 4 green; 1 red".parse().unwrap();
+        let normal = game.normalize();
+        assert_eq!(normal.to_string(), "Game 5: 1 red; 3 red, 3 blue, 1 teal; 4 green");
+        assert_eq!(normal.normalize().to_string(), normal.to_string());
+        assert_eq!(normal.rounds.len(), game.rounds.len());
+        assert_eq!(normal.maxima(), game.maxima());
+
+        let same: Game = "Game 5: 4 green; 1 red; 1 teal, 3 blue, 3 red".parse().unwrap();
+        assert_eq!(game, same);
+        assert_ne!(game, Game { id: 6, ..same.clone() });
+        assert_ne!(game, "Game 5: 4 green; 1 red; 1 teal, 3 blue, 2 red".parse().unwrap());
+
+        let unique: HashSet<Game> = [game, same].into_iter().collect();
+        assert_eq!(unique.len(), 1);
+
+        // Other colors sort by name, whichever was seen first.
+        let late: Game = "Game 9: 1 zucchini, 2 avocado, 3 blue; 1 zucchini".parse().unwrap();
+        assert_eq!(late.normalize().to_string(), "Game 9: 3 blue, 2 avocado, 1 zucchini; 1 zucchini");
+    }
+
+    #[test]
+    fn test_arbitrary_colors() {
+        let line = "Game 4: 2 yellow, 1 red; 3 yellow, 2 green, 1 blue".to_string();
+        let game: Game = line.parse().unwrap();
+        let yellow = ColorId::intern("yellow");
+        assert_eq!(game.max(yellow), 3);
+        assert_eq!(game.blue(), 1);
+        assert_eq!(game.power(), 6);
+        assert!(!game.is_possible(&Config::puzzle()));
+        assert!(game.is_possible(&Config::puzzle().with(yellow, 3)));
+    }
+
+    #[test]
+    fn test_game_keeps_rounds() {
+        let line = "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red".to_string();
+        let game: Game = line.parse().unwrap();
+        assert_eq!(game.rounds.len(), 3);
+        assert_eq!(game.rounds[1], Round {
+            draws: vec![(ColorId::BLUE, 5), (ColorId::RED, 4), (ColorId::GREEN, 13)]
+        });
+        let config = Config::puzzle();
+        let culprit = game.rounds.iter().position(|round| round.count(ColorId::RED) > config.red());
+        assert_eq!(culprit, Some(0));
+    }
+
+    #[test]
+    fn test_games_without_a_file() {
+        let input = include_str!("../input/sample.txt");
+        let from_str = games_from_str(input);
+        assert_eq!(from_str.len(), 5);
+        assert_eq!(from_str, games_from_lines(input.lines().map(String::from)));
+        assert_eq!(from_str[2], Game::from_line(input.lines().nth(2).unwrap()).unwrap());
+        assert_eq!(games_from_str("Game 1: 1 red\nnonsense\n").len(), 1);
+    }
+
+    /// A path in the temp directory named after the test and this process, so
+    /// concurrent test runs don't write over each other's files.
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("cube-game-{}-{name}.txt", std::process::id()))
+    }
+
+    fn write_input(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = temp_path(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_try_games() {
+        let games = try_games("input/sample.txt").unwrap();
+        assert_eq!(games.len(), 5);
+
+        let path = write_input("corrupt", "Game 1: 1 red\n\nGame 2: 2 bl#e\nGame 3: 1 red\n");
+        match try_games(&path) {
+            Err(GamesError::Parse(LineError { line_no, error })) => {
+                assert_eq!(line_no, 3);
+                assert!(matches!(error, GameParseError::UnknownColor { .. }));
+            },
+            _ => panic!("expected a parse error"),
+        }
+        assert!(matches!(try_games("input/missing.txt"), Err(GamesError::Io(_))));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_games_iter_streams_from_any_reader() {
+        let input = "Game 1: 3 red\n\nGame 2: 20 red\nGame x\nGame 4: 1 blue\n";
+        let config = Config::puzzle();
+        let id_sum: u32 = GamesIter::new(input.as_bytes())
+            .filter_map(Result::ok)
+            .filter(|game| game.is_possible(&config))
+            .map(|game| game.id)
+            .sum();
+        assert_eq!(id_sum, 5);
+
+        let results: Vec<Result<Game, GamesError>> = GamesIter::new(input.as_bytes()).collect();
+        assert_eq!(results.len(), 4);
+        assert!(matches!(&results[2], Err(GamesError::Parse(LineError { line_no: 4, .. }))));
+    }
+
+    #[test]
+    fn test_try_games_with() {
+        let path = write_input("modes", "Game 1: 1 red, 2 teal\nGame 2: 3 blue, 1 blue\n");
+        let (games, warnings) = try_games_with(&path, &ParseOptions::default()).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(warnings.iter().map(|w| w.line_no).collect::<Vec<usize>>(), vec![1, 2]);
+        assert!(matches!(warnings[1].error, GameParseError::DuplicateColor { column: 18, .. }));
+
+        match try_games_with(&path, &ParseOptions::strict()) {
+            Err(GamesError::Parse(LineError { line_no: 1, error: GameParseError::UnknownColor { token, .. } })) => {
+                assert_eq!(token, "teal");
+            },
+            _ => panic!("expected teal to be rejected"),
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_possible_games_streams() {
+        let input = include_str!("../input/input.txt");
+        let config = Config::puzzle();
+        let parsed = GamesIter::new(input.as_bytes()).map_while(Result::ok);
+        let id_sum: u32 = PossibleGames::new(parsed, &config).map(|game| game.id).sum();
+        assert_eq!(id_sum, 2810);
+
+        let mut possible = PossibleGames::new(games("input/sample.txt").into_iter(), &config);
+        assert_eq!(possible.size_hint(), (0, Some(5)));
+        assert_eq!(possible.next().map(|game| game.id), Some(1));
+        assert_eq!(possible.nth(1).map(|game| game.id), Some(5));
+        assert_eq!(possible.next(), None);
+    }
+
+    #[test]
+    fn test_games_lenient() {
+        let path = write_input("lenient", "Game 1: 1 red\nGame two: 1 red\nGame 3: 1 red\n");
+        let (games, errors) = games_lenient(&path).unwrap();
+        assert_eq!(games.iter().map(|g| g.id).collect::<Vec<u32>>(), vec![1, 3]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line_no, 2);
+        assert_eq!(errors[0].to_string(), "line 2: expected \"Game <id>: \" at the start of \"Game two: 1 red\"");
+        assert_eq!(errors[0].text(), "Game two: 1 red");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_games_lenient_with_limit() {
+        let path = write_input("limit", "Game 1: 1 red\nGame two\nGame 3: 1 r#d\n\nGame 4: 1 red\nGame 5 1 red\n");
+        let (games, errors) = games_lenient_with_limit(&path, 3).unwrap();
+        assert_eq!(games.len(), 2);
+        let failed: Vec<(usize, &str)> = errors.iter().map(|err| (err.line_no, err.text())).collect();
+        assert_eq!(failed, vec![(2, "Game two"), (3, "Game 3: 1 r#d"), (6, "Game 5 1 red")]);
+
+        match games_lenient_with_limit(&path, 1) {
+            Err(err @ GamesError::TooManyErrors(_)) => {
+                assert!(err.to_string().starts_with("gave up after 2 lines failed to parse; the last was line 3: "));
+                let GamesError::TooManyErrors(errors) = err else { unreachable!() };
+                assert_eq!(errors.iter().map(|err| err.line_no).collect::<Vec<usize>>(), vec![2, 3]);
+            },
+            _ => panic!("expected to give up"),
+        }
+        assert!(matches!(games_lenient_with_limit("input/missing.txt", 0), Err(GamesError::Io(_))));
+        assert_eq!(games_lenient_with_limit("input/sample.txt", 0).unwrap().0.len(), 5);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_games_from_paths() {
+        let first = write_input("merge-1", "Game 1: 1 red\nGame 2: 2 red\n");
+        let second = write_input("merge-2", "Game 2: 3 red\nbad line\nGame 4: 4 red\nGame 1: 5 red\n");
+        let games = games_from_paths(&[first.clone(), second.clone()]).unwrap();
+        let ids: Vec<(u32, u32)> = games.iter().map(|game| (game.id, game.red())).collect();
+        assert_eq!(ids, vec![(1, 1), (2, 2), (3, 3), (4, 4), (5, 5)]);
+
+        assert_eq!(games_from_paths(&[]).unwrap(), vec![]);
+        assert!(games_from_paths(&[first.clone(), PathBuf::from("input/missing.txt")]).is_err());
+
+        let last = write_input("merge-last", "Game 4294967295: 1 red\nGame 1: 1 red\nGame 1: 2 red\n");
+        let err = games_from_paths(std::slice::from_ref(&last)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        for path in [first, second, last] {
+            std::fs::remove_file(path).unwrap();
+        }
     }
 
     #[test]
     fn test_sample() {
-        let config = Config { red: 12, green: 13, blue: 14 };
+        let config = Config::puzzle();
         let result = possible_games("input/sample.txt", &config);
         assert_eq!(result.len(), 3);
         let id_sum: u32 = result.iter().map(|g| g.id).sum();
         assert_eq!(id_sum, 8);
     }
 
+    #[test]
+    fn test_possible_games_with_map() {
+        let map = ConfigMap::new().with(1..=2, Config::puzzle()).with(3..=4, Config::new(20, 13, 14));
+        let ids: Vec<u32> = possible_games_with_map("input/sample.txt", &map).iter().map(|g| g.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+
+        let puzzle = ConfigMap::new().with(.., Config::puzzle());
+        assert_eq!(possible_games_with_map("input/input.txt", &puzzle), possible_games("input/input.txt", &Config::puzzle()));
+    }
+
     #[test]
     fn test_input() {
-        let config = Config { red: 12, green: 13, blue: 14 };
+        let config = Config::puzzle();
         let result = possible_games("input/input.txt", &config);
         let id_sum: u32 = result.iter().map(|g| g.id).sum();
         assert_eq!(id_sum, 2810);
@@ -167,8 +899,77 @@ mod tests {
 
     #[test]
     fn test_game_power() {
-        let game = Game { id: 1, red: 2, green: 4, blue: 6 };
+        let game = game(1, 2, 4, 6);
         assert_eq!(game.power(), 48);
+        assert_eq!(game.checked_power(), Some(48));
+
+        let big: Game = "Game 1: 4000000000 red, 4000000000 green, 1 blue".parse().unwrap();
+        assert_eq!(big.checked_power(), Some(16_000_000_000_000_000_000));
+    }
+
+    #[test]
+    fn test_round_count_saturates() {
+        let game: Game = "Game 1: 4000000000 red, 4000000000 red".parse().unwrap();
+        assert_eq!(game.rounds[0].count(ColorId::RED), u32::MAX);
+        assert_eq!(game.red(), u32::MAX);
+    }
+
+    #[test]
+    fn test_fold_rounds() {
+        let games = games("input/sample.txt");
+        assert_eq!(games[2].rounds().map(Round::total).collect::<Vec<u64>>(), vec![34, 22, 6]);
+        assert_eq!(games[2].max_handful(), 34);
+        assert_eq!(Game { id: 1, rounds: vec![] }.max_handful(), 0);
+
+        let blue_rounds = games[3].fold_rounds(0, |n, round| n + usize::from(round.count(ColorId::BLUE) > 0));
+        assert_eq!(blue_rounds, 2);
+        let longest = games.iter().map(|game| game.fold_rounds(0, |n, round| n.max(round.draws.len()))).max();
+        assert_eq!(longest, Some(3));
+    }
+
+    #[test]
+    fn test_total_shown() {
+        let games = games("input/sample.txt");
+        assert_eq!(games[2].total_shown(ColorId::RED), 25);
+        assert_eq!(games[2].total_shown(ColorId::GREEN), 26);
+        assert_eq!(games[0].total_shown(ColorId::intern("teal")), 0);
+
+        let everything = |game: &Game| -> u64 {
+            [ColorId::RED, ColorId::GREEN, ColorId::BLUE].iter().map(|color| game.total_shown(*color)).sum()
+        };
+        let most = games.iter().max_by_key(|game| everything(game)).unwrap();
+        assert_eq!((most.id, everything(most)), (3, 62));
+
+        let big: Game = "Game 1: 4000000000 red; 4000000000 red".parse().unwrap();
+        assert_eq!(big.total_shown(ColorId::RED), 8_000_000_000);
+    }
+
+    #[test]
+    fn test_first_impossible_round() {
+        let games = games("input/sample.txt");
+        let config = Config::puzzle();
+        assert_eq!(games[0].first_impossible_round(&config), None);
+
+        let (index, round) = games[3].first_impossible_round(&config).unwrap();
+        assert_eq!(index, 2);
+        assert_eq!(round.over_limit(&config), vec![(ColorId::BLUE, 15), (ColorId::RED, 14)]);
+        assert!(games.iter().all(|game| game.is_possible(&config) == game.first_impossible_round(&config).is_none()));
+    }
+
+    #[test]
+    fn test_min_config() {
+        let line = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green".to_string();
+        let game: Game = line.parse().unwrap();
+        let min = game.min_config();
+        assert_eq!(min, Config::new(4, 2, 6));
+        assert!(game.is_possible(&min));
+        assert!(!game.is_possible(&Config::new(3, 2, 6)));
+    }
+
+    #[test]
+    fn test_sum_of_powers() {
+        assert_eq!(sum_of_powers("input/sample.txt"), 2286);
+        assert_eq!(sum_of_powers("input/input.txt"), 69110);
     }
 
     #[test]
@@ -190,6 +991,24 @@ mod tests {
             .map(|game| game.power())
             .reduce(|acc, power| acc + power)
             .unwrap();
-        assert_eq!(sum, 2286);
+        assert_eq!(sum, 69110);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let games = games("input/sample.txt");
+        let json = serde_json::to_string(&games).unwrap();
+        let parsed: Vec<Game> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, games);
+
+        let game: Game = serde_json::from_str(
+            r#"{"id":7,"rounds":[{"draws":[["red",2],["teal",1]]}]}"#
+        ).unwrap();
+        assert_eq!(game.max(ColorId::intern("teal")), 1);
+
+        let config = Config::puzzle();
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(serde_json::from_str::<Config>(&json).unwrap(), config);
     }
 }
@@ -0,0 +1,54 @@
+use std::env;
+use std::process;
+
+use cube_game::{try_games, ColorId, Config, GameSet};
+
+const USAGE: &str = "usage: cube-game --input <path> [--part 1|2] [--red <n>] [--green <n>] [--blue <n>]";
+
+fn usage() -> ! {
+    eprintln!("{USAGE}");
+    process::exit(2);
+}
+
+fn count(value: Option<String>) -> u32 {
+    value.and_then(|value| value.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn main() {
+    let mut input = None;
+    let mut part = 1;
+    let mut bag = Config::puzzle();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => input = Some(args.next().unwrap_or_else(|| usage())),
+            "--part" => part = count(args.next()),
+            "--red" => bag.set(ColorId::RED, count(args.next())),
+            "--green" => bag.set(ColorId::GREEN, count(args.next())),
+            "--blue" => bag.set(ColorId::BLUE, count(args.next())),
+            _ => usage(),
+        }
+    }
+    let Some(input) = input else { usage() };
+
+    let set: GameSet = match try_games(&input) {
+        Ok(games) => games.into_iter().collect(),
+        Err(err) => {
+            eprintln!("{input}: {err}");
+            process::exit(1);
+        }
+    };
+
+    let answer = match part {
+        1 => Some(set.possible(&bag).ids_sum()),
+        2 => set.checked_power_sum(),
+        _ => usage(),
+    };
+    match answer {
+        Some(answer) => println!("{answer}"),
+        None => {
+            eprintln!("{input}: answer overflows");
+            process::exit(1);
+        }
+    }
+}
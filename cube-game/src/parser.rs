@@ -0,0 +1,329 @@
+use nom::{
+    bytes::complete::{tag, take_till1},
+    character::complete::{char, digit1, space0, space1},
+    combinator::{all_consuming, opt},
+    error::{ErrorKind, ParseError},
+    multi::separated_list1,
+    sequence::terminated,
+    Err, IResult,
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::{ColorId, Game, GameParseError, Round};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Problem {
+    Header,
+    Id,
+    Count,
+    Color,
+    Duplicate,
+    Garbage,
+    TooManyColors,
+}
+
+/// Where parsing went wrong: the offending token, which is a subslice of the
+/// line so its offset can be recovered.
+#[derive(Debug)]
+struct Failure<'a> {
+    token: &'a str,
+    problem: Problem,
+}
+
+impl<'a> ParseError<&'a str> for Failure<'a> {
+    fn from_error_kind(input: &'a str, _kind: ErrorKind) -> Self {
+        Failure { token: draw_text(input), problem: Problem::Count }
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+type Res<'a, T> = IResult<&'a str, T, Failure<'a>>;
+
+/// The rest of the draw starting at `input`, up to the next separator.
+fn draw_text(input: &str) -> &str {
+    let end = input.find([',', ';']).unwrap_or(input.len());
+    input[..end].trim()
+}
+
+fn fail<T>(token: &str, problem: Problem) -> Res<'_, T> {
+    Err(Err::Failure(Failure { token, problem }))
+}
+
+fn number<'a>(input: &'a str, problem: Problem) -> Res<'a, u32> {
+    let (rest, digits) = digit1(input)?;
+    match digits.parse() {
+        Ok(n) => Ok((rest, n)),
+        Err(_) => fail(digits, problem),
+    }
+}
+
+fn header(input: &str) -> Res<'_, u32> {
+    let missing = |err| match err {
+        Err::Error(_) => Err::Failure(Failure { token: input, problem: Problem::Header }),
+        err => err,
+    };
+    let (rest, _) = tag("Game ")(input).map_err(missing)?;
+    let (rest, id) = number(rest, Problem::Id).map_err(missing)?;
+    let (rest, _) = tag(": ")(rest).map_err(missing)?;
+    Ok((rest, id))
+}
+
+/// How the parser treats lines it can make sense of that aren't quite
+/// well-formed: colors it wasn't told about, a color listed twice in one
+/// round, or stray text after a draw's color.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ParseMode {
+    /// Each of those is an error.
+    Strict,
+    /// Each of those is accepted and reported as a warning.
+    #[default]
+    Lenient,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseOptions {
+    pub mode: ParseMode,
+    /// The colors that aren't unknown. Red, green and blue by default.
+    pub colors: Vec<ColorId>,
+    /// Read color names, and aliases, without regard to case. Names are
+    /// lowercased before they're interned.
+    pub ignore_case: bool,
+    /// Other names for colors, e.g. `"r"` for red.
+    pub aliases: HashMap<String, ColorId>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            mode: ParseMode::default(),
+            colors: vec![ColorId::RED, ColorId::GREEN, ColorId::BLUE],
+            ignore_case: false,
+            aliases: HashMap::new(),
+        }
+    }
+}
+
+impl ParseOptions {
+    pub fn strict() -> Self {
+        Self { mode: ParseMode::Strict, ..Self::default() }
+    }
+
+    pub fn with_alias(mut self, alias: &str, color: ColorId) -> Self {
+        self.aliases.insert(alias.to_string(), color);
+        self
+    }
+
+    /// The color a name in the input stands for, or `None` if it's new and
+    /// there's no room left to intern it.
+    fn resolve(&self, name: &str) -> Option<ColorId> {
+        if !self.ignore_case {
+            return self.aliases.get(name).copied().or_else(|| ColorId::try_intern(name));
+        }
+        let name = name.to_lowercase();
+        self.aliases
+            .iter()
+            .find(|(alias, _)| alias.to_lowercase() == name)
+            .map(|(_, color)| *color)
+            .or_else(|| ColorId::try_intern(&name))
+    }
+}
+
+struct Parser<'a> {
+    options: &'a ParseOptions,
+    warnings: RefCell<Vec<Failure<'a>>>,
+}
+
+impl<'a> Parser<'a> {
+    /// Fails on `problem` in strict mode; notes it and carries on in lenient
+    /// mode.
+    fn complain(&self, token: &'a str, problem: Problem) -> Result<(), Err<Failure<'a>>> {
+        match self.options.mode {
+            ParseMode::Strict => Err(Err::Failure(Failure { token, problem })),
+            ParseMode::Lenient => {
+                self.warnings.borrow_mut().push(Failure { token, problem });
+                Ok(())
+            },
+        }
+    }
+
+    /// `<count> <color>`, returning the color's text alongside the draw. A
+    /// blank draw is a recoverable error so that it can be skipped; anything
+    /// else malformed is a failure.
+    fn draw(&self, input: &'a str) -> Res<'a, (&'a str, (ColorId, u32))> {
+        let (input, _) = space0(input)?;
+        if draw_text(input).is_empty() {
+            return Err(Err::Error(Failure { token: input, problem: Problem::Count }));
+        }
+        let bad_count = |err| match err {
+            Err::Error(_) => Err::Failure(Failure { token: draw_text(input), problem: Problem::Count }),
+            err => err,
+        };
+        let (rest, count) = number(input, Problem::Count).map_err(bad_count)?;
+        let (rest, _) = space1(rest).map_err(bad_count)?;
+        let (rest, name) = take_till1(|c: char| c == ',' || c == ';' || c.is_whitespace())(rest)
+            .map_err(bad_count)?;
+        if !name.chars().all(char::is_alphabetic) {
+            return fail(name, Problem::Color);
+        }
+        let Some(color) = self.options.resolve(name) else {
+            return fail(name, Problem::TooManyColors);
+        };
+        if !self.options.colors.contains(&color) {
+            self.complain(name, Problem::Color)?;
+        }
+        let (mut rest, _) = space0(rest)?;
+        if !rest.is_empty() && !rest.starts_with([',', ';']) {
+            let garbage = draw_text(rest);
+            self.complain(garbage, Problem::Garbage)?;
+            rest = space0(&rest[garbage.len()..])?.0;
+        }
+        Ok((rest, (name, (color, count))))
+    }
+
+    fn round(&self, input: &'a str) -> Res<'a, Round> {
+        let (rest, draws) = separated_list1(char(','), opt(|input| self.draw(input)))(input)?;
+        let (rest, _) = space0(rest)?;
+        let draws: Vec<(&str, (ColorId, u32))> = draws.into_iter().flatten().collect();
+        for (index, (name, (color, _))) in draws.iter().enumerate() {
+            if draws[..index].iter().any(|(_, (earlier, _))| earlier == color) {
+                self.complain(name, Problem::Duplicate)?;
+            }
+        }
+        Ok((rest, Round { draws: draws.into_iter().map(|(_, draw)| draw).collect() }))
+    }
+
+    fn game(&self, input: &'a str) -> Res<'a, Game> {
+        let (rest, id) = header(input)?;
+        let (rest, rounds) = terminated(separated_list1(char(';'), |input| self.round(input)), space0)(rest)?;
+        Ok((rest, Game { id, rounds }))
+    }
+}
+
+/// Byte offset of `part`, a subslice of `line`, within `line`.
+fn offset_in(line: &str, part: &str) -> usize {
+    part.as_ptr() as usize - line.as_ptr() as usize
+}
+
+fn error(line: &str, failure: Failure<'_>) -> GameParseError {
+    let line_string = line.to_string();
+    let column = offset_in(line, failure.token);
+    let token = failure.token.to_string();
+    match failure.problem {
+        Problem::Header => GameParseError::MissingHeader { line: line_string },
+        Problem::Id => GameParseError::BadId { line: line_string, token },
+        Problem::Count => GameParseError::BadCount { line: line_string, column, token },
+        Problem::Color => GameParseError::UnknownColor { line: line_string, column, token },
+        Problem::Duplicate => GameParseError::DuplicateColor { line: line_string, column, token },
+        Problem::Garbage => GameParseError::TrailingGarbage { line: line_string, column, token },
+        Problem::TooManyColors => GameParseError::TooManyColors { line: line_string, column, token },
+    }
+}
+
+/// Parses `line`, returning the game alongside any problems that lenient mode
+/// let through.
+pub(crate) fn parse_game(
+    line: &str,
+    options: &ParseOptions,
+) -> Result<(Game, Vec<GameParseError>), GameParseError> {
+    let parser = Parser { options, warnings: RefCell::new(vec![]) };
+    let result = all_consuming(|input| parser.game(input))(line);
+    match result {
+        Ok((_, game)) => {
+            let warnings = parser.warnings.into_inner().into_iter().map(|warning| error(line, warning));
+            Ok((game, warnings.collect()))
+        },
+        Err(Err::Error(failure) | Err::Failure(failure)) => Err(error(line, failure)),
+        Err(Err::Incomplete(_)) => unreachable!("complete parsers never ask for more input"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<(Game, Vec<GameParseError>), GameParseError> {
+        parse_game(line, &ParseOptions::default())
+    }
+
+    fn parse_strict(line: &str) -> Result<(Game, Vec<GameParseError>), GameParseError> {
+        parse_game(line, &ParseOptions::strict())
+    }
+
+    #[test]
+    fn test_blank_draws_and_rounds_are_skipped() {
+        let (game, warnings) = parse("Game 1: 1 red,, 2 blue;").unwrap();
+        assert_eq!(game.rounds.len(), 2);
+        assert_eq!(game.rounds[0].draws, vec![(ColorId::RED, 1), (ColorId::BLUE, 2)]);
+        assert!(game.rounds[1].draws.is_empty());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_trailing_garbage_is_spanned() {
+        let line = "Game 1: 3 red blue; 2 green";
+        let garbage = GameParseError::TrailingGarbage { line: line.to_string(), column: 14, token: "blue".to_string() };
+        assert_eq!(parse_strict(line).unwrap_err(), garbage);
+        assert_eq!(garbage.span(), Some(14..18));
+
+        let (game, warnings) = parse(line).unwrap();
+        assert_eq!(game.to_string(), "Game 1: 3 red; 2 green");
+        assert_eq!(warnings, vec![garbage]);
+    }
+
+    #[test]
+    fn test_unknown_colors() {
+        let line = "Game 1: 3 red, 2 teal";
+        let unknown = GameParseError::UnknownColor { line: line.to_string(), column: 17, token: "teal".to_string() };
+        assert_eq!(parse_strict(line).unwrap_err(), unknown);
+        let (game, warnings) = parse(line).unwrap();
+        assert_eq!(game.max(ColorId::intern("teal")), 2);
+        assert_eq!(warnings, vec![unknown]);
+
+        let teal = ParseOptions { colors: vec![ColorId::RED, ColorId::intern("teal")], ..ParseOptions::strict() };
+        assert!(parse_game(line, &teal).unwrap().1.is_empty());
+    }
+
+    #[test]
+    fn test_aliases_and_case() {
+        let options = ParseOptions::strict()
+            .with_alias("r", ColorId::RED)
+            .with_alias("G", ColorId::GREEN);
+        let (game, _) = parse_game("Game 1: 3 r, 2 G, 1 blue", &options).unwrap();
+        assert_eq!((game.red(), game.green(), game.blue()), (3, 2, 1));
+        assert!(parse_game("Game 1: 3 R", &options).is_err());
+        assert!(parse_game("Game 1: 3 Blue", &options).is_err());
+
+        let options = ParseOptions { ignore_case: true, ..options };
+        let (game, _) = parse_game("Game 1: 3 R, 2 g, 1 BLUE; 4 Red", &options).unwrap();
+        assert_eq!((game.red(), game.green(), game.blue()), (4, 2, 1));
+        assert_eq!(game.to_string(), "Game 1: 3 red, 2 green, 1 blue; 4 red");
+        assert!(matches!(
+            parse_game("Game 1: 3 r, 1 RED", &options),
+            Err(GameParseError::DuplicateColor { column: 15, .. }),
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_colors() {
+        let line = "Game 1: 3 red, 2 blue, 4 red; 1 red";
+        let duplicate = GameParseError::DuplicateColor { line: line.to_string(), column: 25, token: "red".to_string() };
+        assert_eq!(parse_strict(line).unwrap_err(), duplicate);
+        let (game, warnings) = parse(line).unwrap();
+        assert_eq!(game.red(), 7);
+        assert_eq!(warnings, vec![duplicate]);
+    }
+
+    #[test]
+    fn test_well_formed_input_is_clean_in_either_mode() {
+        for line in include_str!("../input/input.txt").lines() {
+            let (strict, warnings) = parse_strict(line).unwrap();
+            assert!(warnings.is_empty());
+            assert_eq!(parse(line).unwrap(), (strict, vec![]));
+        }
+    }
+}
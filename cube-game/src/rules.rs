@@ -0,0 +1,90 @@
+use crate::{Config, ConfigMap, Game};
+
+/// Decides whether a game could have been played. A `Config` is the puzzle's
+/// rule: no color may ever show more cubes than the bag holds.
+pub trait PossibilityRule {
+    fn allows(&self, game: &Game) -> bool;
+}
+
+impl PossibilityRule for Config {
+    fn allows(&self, game: &Game) -> bool {
+        game.is_possible(self)
+    }
+}
+
+/// A game is possible with the bag for its id. A game no bag covers isn't
+/// possible.
+impl PossibilityRule for ConfigMap {
+    fn allows(&self, game: &Game) -> bool {
+        self.get(game.id).is_some_and(|config| game.is_possible(config))
+    }
+}
+
+impl<F> PossibilityRule for F
+where F: Fn(&Game) -> bool {
+    fn allows(&self, game: &Game) -> bool {
+        self(game)
+    }
+}
+
+/// No round may show more than this many cubes, of all colors together.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MaxHandful(pub u32);
+
+impl PossibilityRule for MaxHandful {
+    fn allows(&self, game: &Game) -> bool {
+        game.max_handful() <= u64::from(self.0)
+    }
+}
+
+/// The smallest bag the game needs may hold no more than this many cubes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MaxCubes(pub u64);
+
+impl PossibilityRule for MaxCubes {
+    fn allows(&self, game: &Game) -> bool {
+        game.min_config().total() <= self.0
+    }
+}
+
+/// Each cube the game needs costs its color's weight; the total cost must fit
+/// in the budget. Colors without a weight are free.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WeightedBudget {
+    pub weights: Config,
+    pub budget: u64,
+}
+
+impl PossibilityRule for WeightedBudget {
+    fn allows(&self, game: &Game) -> bool {
+        let cost: u64 = game.maxima()
+            .iter()
+            .map(|(color, max)| u64::from(self.weights.get(*color)) * u64::from(*max))
+            .sum();
+        cost <= self.budget
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameSet;
+
+    #[test]
+    fn test_config_is_the_default_rule() {
+        let set = GameSet::from_path("input/sample.txt");
+        assert_eq!(set.possible_under(&Config::puzzle()), set.possible(&Config::puzzle()));
+    }
+
+    #[test]
+    fn test_alternative_rules() {
+        let set = GameSet::from_path("input/sample.txt");
+        let ids = |set: GameSet| set.iter().map(|game| game.id).collect::<Vec<u32>>();
+
+        assert_eq!(ids(set.possible_under(&MaxHandful(9))), vec![1, 2]);
+        assert_eq!(ids(set.possible_under(&MaxCubes(12))), vec![1, 2, 5]);
+        let weights = Config::new(1, 1, 10);
+        assert_eq!(ids(set.possible_under(&WeightedBudget { weights, budget: 45 })), vec![2, 5]);
+        assert_eq!(ids(set.possible_under(&|game: &Game| game.rounds.len() == 2)), vec![5]);
+    }
+}
@@ -0,0 +1,115 @@
+//! Generates synthetic games by drawing cubes from a bag, for property tests
+//! and benchmark inputs. Each round draws cubes without replacement; the cubes
+//! go back in the bag between rounds, as in the puzzle.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::{ColorId, Config, Game, Round};
+
+/// Draws between one cube and the whole bag. An empty bag gives an empty
+/// round. Takes time in proportion to the cubes drawn, but only keeps a
+/// count per color.
+pub fn round<R: Rng + ?Sized>(bag: &Config, rng: &mut R) -> Round {
+    let mut left: Vec<(ColorId, u64)> = bag.iter().map(|(color, count)| (color, u64::from(count))).collect();
+    left.sort_by_key(|(color, _)| color.canonical_key());
+    let total: u64 = left.iter().map(|(_, count)| count).sum();
+    if total == 0 {
+        return Round::default();
+    }
+    let handful = rng.random_range(1..=total);
+
+    // Each cube is picked with weight equal to how many of its color are
+    // still in the bag, without laying the cubes out one by one.
+    let mut drawn = vec![0u32; left.len()];
+    for remaining in (total - handful + 1..=total).rev() {
+        let mut pick = rng.random_range(0..remaining);
+        for (index, (_, count)) in left.iter_mut().enumerate() {
+            if pick < *count {
+                *count -= 1;
+                drawn[index] += 1;
+                break;
+            }
+            pick -= *count;
+        }
+    }
+
+    let mut draws: Vec<(ColorId, u32)> = left
+        .iter()
+        .zip(drawn)
+        .map(|((color, _), count)| (*color, count))
+        .filter(|(_, count)| *count > 0)
+        .collect();
+    draws.shuffle(rng);
+    Round { draws }
+}
+
+/// A game of between one and `max_rounds` rounds.
+pub fn game<R: Rng + ?Sized>(id: u32, bag: &Config, max_rounds: usize, rng: &mut R) -> Game {
+    let rounds = rng.random_range(1..=max_rounds.max(1));
+    Game {
+        id,
+        rounds: (0..rounds).map(|_| round(bag, rng)).collect(),
+    }
+}
+
+/// `count` games numbered from 1, written one per line in the puzzle's syntax.
+pub fn input<R: Rng + ?Sized>(bag: &Config, count: u32, max_rounds: usize, rng: &mut R) -> String {
+    (1..=count)
+        .map(|id| format!("{}\n", game(id, bag, max_rounds, rng)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games_from_str;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_simulated_games_fit_their_bag() {
+        let mut rng = StdRng::seed_from_u64(2023);
+        let bag = Config::puzzle().with(ColorId::intern("violet"), 3);
+        for id in 1..=200 {
+            let game = game(id, &bag, 6, &mut rng);
+            assert!(game.is_possible(&bag));
+            assert!(game.rounds.iter().all(|round| !round.draws.is_empty()));
+        }
+    }
+
+    #[test]
+    fn test_simulated_input_parses() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let bag = Config::new(12, 13, 14);
+        let input = input(&bag, 50, 5, &mut rng);
+        let games = games_from_str(&input);
+        assert_eq!(games.len(), 50);
+        let smaller = Config::new(6, 6, 6);
+        assert!(games.iter().all(|game| game.is_possible(&bag)));
+        assert!(games.iter().any(|game| !game.is_possible(&smaller)));
+    }
+
+    #[test]
+    fn test_same_seed_same_games() {
+        let bag = Config::puzzle();
+        let a = input(&bag, 10, 4, &mut StdRng::seed_from_u64(1));
+        let b = input(&bag, 10, 4, &mut StdRng::seed_from_u64(1));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_empty_bag() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(round(&Config::default(), &mut rng), Round::default());
+    }
+
+    #[test]
+    fn test_huge_bag() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let bag = Config::new(1_000_000, 0, 1);
+        let round = round(&bag, &mut rng);
+        assert!(round.draws.iter().all(|(color, count)| *count <= bag.get(*color)));
+        assert!(!round.draws.is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use engine_schematic_part_numbers::{Finalized, Schematic, SchematicOptions, Storage, Stream};
+use std::hint::black_box;
+
+/// A schematic of `height` lines `width` wide, scattering numbers and symbols
+/// with a fixed linear congruential generator so every run sees the same
+/// input.
+fn synthetic(width: usize, height: usize) -> String {
+    let mut state: u64 = 2023;
+    let mut next = move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 33) as usize
+    };
+    let mut input = String::with_capacity(height * (width + 1));
+    for _ in 0..height {
+        let mut line = String::with_capacity(width);
+        while line.len() < width {
+            match next() % 10 {
+                0..=4 => line.push('.'),
+                5..=7 => line.push_str(&(next() % 1000).to_string()),
+                _ => line.push(['*', '#', '+', '$', '/'][next() % 5]),
+            }
+            line.push('.');
+        }
+        line.truncate(width);
+        input.push_str(&line);
+        input.push('\n');
+    }
+    input
+}
+
+fn adjacency(c: &mut Criterion) {
+    let input = synthetic(1000, 1000);
+    let mut group = c.benchmark_group("adjacency");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.sample_size(10);
+    group.bench_function("parse_and_sum", |b| {
+        b.iter(|| {
+            let schematic = Schematic::parse(black_box(&input));
+            let parts = schematic.part_number_sum();
+            let gears = schematic.gear_ratio_sum();
+            (parts, gears)
+        })
+    });
+    group.bench_function("stream", |b| {
+        b.iter(|| {
+            Stream::new(black_box(&input).lines())
+                .filter(|item| matches!(item, Finalized::Part(_)))
+                .count()
+        })
+    });
+    group.finish();
+}
+
+/// A few very wide lines, where each number still only looks at the cells
+/// around it rather than at every vertex on the lines either side.
+fn wide_rows(c: &mut Criterion) {
+    let input = synthetic(20_000, 3);
+    let mut group = c.benchmark_group("wide_rows");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    for storage in [Storage::Dense, Storage::Sparse] {
+        let options = SchematicOptions { storage, ..Default::default() };
+        group.bench_function(format!("{storage:?}"), |b| {
+            b.iter(|| Schematic::parse_with(black_box(&input), &options).part_number_sum())
+        });
+    }
+    group.finish();
+}
+
+/// Parsing, then each answer from an already parsed schematic, timed apart
+/// so a change to one doesn't hide in the others.
+fn phases(c: &mut Criterion) {
+    let mut group = c.benchmark_group("phases");
+    group.sample_size(10);
+    for size in [1000, 5000] {
+        let input = synthetic(size, size);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_function(format!("parse/{size}"), |b| b.iter(|| Schematic::parse(black_box(&input))));
+        let schematic = Schematic::parse(&input);
+        group.bench_function(format!("part_numbers/{size}"), |b| b.iter(|| black_box(&schematic).part_numbers()));
+        group.bench_function(format!("gear_ratios/{size}"), |b| b.iter(|| black_box(&schematic).gear_ratios()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, adjacency, wide_rows, phases);
+criterion_main!(benches);
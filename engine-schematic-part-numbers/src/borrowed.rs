@@ -0,0 +1,150 @@
+//! A schematic read straight out of a byte buffer, such as a memory-mapped
+//! file, without copying its lines. Every byte is a cell, and numbers are
+//! only parsed when asked for. Only the puzzle's rules are supported: `.`
+//! is blank, numbers are decimal, adjacency is eight-cell and edges never
+//! wrap.
+
+/// A number or symbol, borrowing its bytes from the input.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Span<'a> {
+    pub text: &'a [u8],
+    pub y: usize,
+    pub min_x: usize,
+    is_symbol: bool,
+}
+
+impl<'a> Span<'a> {
+    pub fn max_x(&self) -> usize {
+        self.min_x + self.text.len() - 1
+    }
+
+    /// The number, if it is one and fits in a `u64`.
+    pub fn number(&self) -> Option<u64> {
+        if self.is_symbol {
+            return None;
+        }
+        parse_digits(self.text)
+    }
+
+    pub fn symbol(&self) -> Option<u8> {
+        self.is_symbol.then_some(self.text[0])
+    }
+}
+
+/// A schematic whose numbers and symbols borrow from the input they were
+/// read from.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BorrowedSchematic<'a> {
+    /// Every number and symbol, in reading order.
+    spans: Vec<Span<'a>>,
+    /// Where each row's spans start, then where the last row's end.
+    starts: Vec<usize>,
+}
+
+impl<'a> BorrowedSchematic<'a> {
+    /// Reads `input`'s lines, split on `\n` with any `\r` before it dropped.
+    pub fn parse(input: &'a [u8]) -> Self {
+        let input = input.strip_suffix(b"\n").unwrap_or(input);
+        let mut spans = vec![];
+        let mut starts = vec![];
+        for (y, line) in input.split(|byte| *byte == b'\n').enumerate() {
+            starts.push(spans.len());
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            let mut x = 0;
+            while x < line.len() {
+                let digits = line[x..].iter().take_while(|byte| byte.is_ascii_digit()).count();
+                let len = digits.max(1);
+                if line[x] != b'.' {
+                    spans.push(Span { text: &line[x..x + len], y, min_x: x, is_symbol: digits == 0 });
+                }
+                x += len;
+            }
+        }
+        starts.push(spans.len());
+        Self { spans, starts }
+    }
+
+    /// Every number and symbol, in reading order.
+    pub fn spans(&self) -> &[Span<'a>] {
+        &self.spans
+    }
+
+    /// The numbers and symbols touching `span`, in reading order.
+    pub fn adjacent<'s>(&'s self, span: &'s Span<'a>) -> impl Iterator<Item = &'s Span<'a>> {
+        let (min_x, max_x) = (span.min_x.saturating_sub(1), span.max_x() + 1);
+        (span.y.saturating_sub(1)..=span.y + 1).flat_map(move |y| {
+            let row = match (self.starts.get(y), self.starts.get(y + 1)) {
+                (Some(start), Some(end)) => &self.spans[*start..*end],
+                _ => &[],
+            };
+            let start = row.partition_point(|other| other.max_x() < min_x);
+            row[start..]
+                .iter()
+                .take_while(move |other| other.min_x <= max_x)
+                .filter(move |other| *other != span)
+        })
+    }
+
+    /// Numbers adjacent to a symbol, in reading order.
+    pub fn part_numbers(&self) -> Vec<u64> {
+        self.spans
+            .iter()
+            .filter(|span| span.symbol().is_none())
+            .filter(|span| self.adjacent(span).any(|other| other.symbol().is_some()))
+            .filter_map(Span::number)
+            .collect()
+    }
+
+    /// The ratio of each of the puzzle's gears. A ratio too large for a `u64`
+    /// saturates at `u64::MAX`.
+    pub fn gear_ratios(&self) -> Vec<u64> {
+        self.spans
+            .iter()
+            .filter(|span| span.symbol() == Some(b'*'))
+            .filter_map(|span| {
+                let numbers: Vec<u64> = self.adjacent(span).filter_map(Span::number).collect();
+                (numbers.len() == 2).then(|| numbers[0].saturating_mul(numbers[1]))
+            })
+            .collect()
+    }
+}
+
+fn parse_digits(digits: &[u8]) -> Option<u64> {
+    digits.iter().try_fold(0u64, |n, digit| n.checked_mul(10)?.checked_add(u64::from(digit - b'0')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Schematic;
+
+    #[test]
+    fn test_matches_schematic() {
+        for input in [include_str!("../input/sample.txt"), include_str!("../input/input.txt")] {
+            let borrowed = BorrowedSchematic::parse(input.as_bytes());
+            let schematic = Schematic::parse(input);
+            assert_eq!(borrowed.part_numbers(), schematic.part_numbers());
+            assert_eq!(borrowed.gear_ratios(), schematic.gear_ratios());
+        }
+    }
+
+    #[test]
+    fn test_spans_borrow_the_input() {
+        let input = b"467..\r\n...*.\r\n..35.\r\n";
+        let borrowed = BorrowedSchematic::parse(input);
+        let spans = borrowed.spans();
+        assert_eq!(spans.len(), 3);
+        assert_eq!(spans[0].text.as_ptr(), input.as_ptr());
+        assert_eq!((spans[2].y, spans[2].min_x, spans[2].max_x()), (2, 2, 3));
+        assert_eq!(borrowed.gear_ratios(), vec![467 * 35]);
+
+        let huge = "18446744073709551616*1";
+        let borrowed = BorrowedSchematic::parse(huge.as_bytes());
+        assert_eq!(borrowed.spans()[0].symbol(), None);
+        assert_eq!(borrowed.spans()[0].number(), None);
+        assert_eq!(borrowed.spans()[0].max_x(), 19);
+        assert_eq!(borrowed.part_numbers(), vec![1]);
+        assert_eq!(borrowed.part_numbers(), Schematic::parse(huge).part_numbers());
+        assert!(BorrowedSchematic::parse(b"").spans().is_empty());
+    }
+}
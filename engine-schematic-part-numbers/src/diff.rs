@@ -0,0 +1,102 @@
+use std::cmp::Ordering;
+
+use crate::{Schematic, Vertex};
+
+/// The result of `Schematic::diff`. Numbers and symbols are matched up by
+/// the cell they start at.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SchematicDiff {
+    /// Numbers and symbols starting at a cell where the older schematic has
+    /// none.
+    pub added: Vec<Vertex>,
+    /// Numbers and symbols starting at a cell where the newer schematic has
+    /// none.
+    pub removed: Vec<Vertex>,
+    pub changed: Vec<VertexChange>,
+    /// How much the part number sum went up or down, or `None` if either
+    /// sum overflows.
+    pub part_number_sum_delta: Option<i128>,
+    /// How much the gear ratio sum went up or down, or `None` if either sum
+    /// overflows.
+    pub gear_ratio_sum_delta: Option<i128>,
+}
+
+impl SchematicDiff {
+    /// Whether no number or symbol was added, removed or changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// A number or symbol starting at the same cell in both schematics that
+/// isn't the same in each.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct VertexChange {
+    pub before: Vertex,
+    pub after: Vertex,
+}
+
+impl Schematic {
+    /// What changed going from this schematic to `other`.
+    pub fn diff(&self, other: &Schematic) -> SchematicDiff {
+        let mut diff = SchematicDiff::default();
+        let mut before = self.vertices().peekable();
+        let mut after = other.vertices().peekable();
+        loop {
+            let order = match (before.peek(), after.peek()) {
+                (Some(b), Some(a)) => (b.y, b.min_x).cmp(&(a.y, a.min_x)),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+            match order {
+                Ordering::Less => diff.removed.extend(before.next()),
+                Ordering::Greater => diff.added.extend(after.next()),
+                Ordering::Equal => {
+                    let (b, a) = (before.next(), after.next());
+                    if let (Some(b), Some(a)) = (b, a) {
+                        if b != a {
+                            diff.changed.push(VertexChange { before: *b, after: *a });
+                        }
+                    }
+                },
+            }
+        }
+        let delta = |before: Option<u64>, after: Option<u64>| Some(i128::from(after?) - i128::from(before?));
+        diff.part_number_sum_delta = delta(self.part_number_sum().ok(), other.part_number_sum().ok());
+        diff.gear_ratio_sum_delta = delta(self.gear_ratio_sum().ok(), other.gear_ratio_sum().ok());
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let sample = include_str!("../input/sample.txt");
+        let before = Schematic::parse(sample);
+        let mut after = before.clone();
+        assert!(before.diff(&after).is_empty());
+        assert_eq!(before.diff(&after).part_number_sum_delta, Some(0));
+
+        // 114 becomes a part number, 35 becomes 3, and a new * appears.
+        after.set_cell(4, 0, '#');
+        after.set_cell(3, 2, '.');
+        after.set_cell(9, 9, '*');
+        let diff = before.diff(&after);
+        assert_eq!(diff.added, vec![Vertex::symbol('#', 0, 4), Vertex::symbol('*', 9, 9)]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed, vec![VertexChange { before: Vertex::number(35, 2, 2), after: Vertex::number(3, 2, 2) }]);
+        assert_eq!(diff.part_number_sum_delta, Some(114 - 32));
+        assert_eq!(diff.gear_ratio_sum_delta, Some(467 * 3 - 467 * 35));
+
+        let reverse = after.diff(&before);
+        assert_eq!(reverse.removed, diff.added);
+        assert_eq!(reverse.part_number_sum_delta, Some(32 - 114));
+
+        let huge = Schematic::parse("18446744073709551615*1");
+        assert_eq!(before.diff(&huge).part_number_sum_delta, None);
+    }
+}
@@ -0,0 +1,88 @@
+use std::ops::Range;
+
+use crate::{Storage, Vertex};
+
+/// A vertex's index into the schematic's vertices, kept small since every
+/// cell and every adjacency holds one.
+pub(crate) type VertexId = u32;
+
+/// Finds which vertex, by index into the schematic's vertices, covers a cell.
+/// The vertices are in reading order, so each row's are a contiguous run.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Grid {
+    /// Each row's width, in cells.
+    widths: Vec<usize>,
+    /// Where each row's vertices start, then where the last row's end.
+    starts: Vec<usize>,
+    /// With dense storage, the vertex covering each cell. Without it, cells
+    /// are found by binary search through their row's vertices.
+    cells: Option<Vec<Vec<Option<VertexId>>>>,
+}
+
+impl Grid {
+    pub(crate) fn new(storage: Storage, widths: Vec<usize>, vertices: &[Vertex]) -> Self {
+        assert!(vertices.len() <= VertexId::MAX as usize, "too many numbers and symbols");
+        let starts = (0..=widths.len())
+            .map(|y| vertices.partition_point(|vertex| vertex.y < y))
+            .collect();
+        let cells = (storage == Storage::Dense).then(|| {
+            let mut cells: Vec<Vec<Option<VertexId>>> = widths.iter().map(|width| vec![None; *width]).collect();
+            for (index, vertex) in vertices.iter().enumerate() {
+                cells[vertex.y][vertex.span()].fill(Some(index as VertexId));
+            }
+            cells
+        });
+        Self { widths, starts, cells }
+    }
+
+    pub(crate) fn get(&self, vertices: &[Vertex], x: usize, y: usize) -> Option<usize> {
+        if let Some(cells) = &self.cells {
+            return cells.get(y)?.get(x)?.map(|id| id as usize);
+        }
+        let row = self.row(y)?;
+        let index = row.start + vertices[row.clone()].partition_point(|vertex| vertex.max_x < x);
+        (index < row.end && vertices[index].min_x <= x).then_some(index)
+    }
+
+    pub(crate) fn id(&self, vertices: &[Vertex], x: usize, y: usize) -> Option<VertexId> {
+        self.get(vertices, x, y).map(|index| index as VertexId)
+    }
+
+    /// The indices of row `y`'s vertices.
+    pub(crate) fn row(&self, y: usize) -> Option<Range<usize>> {
+        Some(*self.starts.get(y)?..*self.starts.get(y + 1)?)
+    }
+
+    pub(crate) fn width(&self) -> usize {
+        self.widths.iter().copied().max().unwrap_or(0)
+    }
+
+    pub(crate) fn height(&self) -> usize {
+        self.widths.len()
+    }
+
+    /// Updates row `y` after its vertices, which were at `old`, were replaced
+    /// by `vertices[old.start..end]`, and it became `width` wide.
+    pub(crate) fn replace_row(
+        &mut self,
+        y: usize,
+        width: usize,
+        old: Range<usize>,
+        end: usize,
+        vertices: &[Vertex],
+    ) {
+        let renumber = |index: usize| if index >= old.end { index + end - old.end } else { index };
+        self.widths[y] = width;
+        self.starts[y + 1..].iter_mut().for_each(|start| *start = renumber(*start));
+        if let Some(cells) = &mut self.cells {
+            for row in &mut cells[y + 1..] {
+                row.iter_mut().flatten().for_each(|id| *id = renumber(*id as usize) as VertexId);
+            }
+            let mut row = vec![None; width];
+            for (index, vertex) in vertices.iter().enumerate().take(end).skip(old.start) {
+                row[vertex.span()].fill(Some(index as VertexId));
+            }
+            cells[y] = row;
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{Gear, Schematic, SymbolRef, Vertex, GEAR_ARITY, GEAR_SYMBOL};
+
+/// What deleting one symbol would change, from
+/// `Schematic::impact_of_removing`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RemovalImpact {
+    pub symbol: SymbolRef,
+    /// The numbers that only it made part numbers, in reading order.
+    pub lost_parts: Vec<Vertex>,
+    /// It, if it was one of the puzzle's gears.
+    pub lost_gear: Option<Gear>,
+    /// How much the part number sum would go down by, as a negative number.
+    pub part_number_sum_delta: i128,
+    /// How much the gear ratio sum would go down by, or `None` if the lost
+    /// gear's ratio overflows.
+    pub gear_ratio_sum_delta: Option<i128>,
+}
+
+impl Schematic {
+    /// What deleting the symbol covering `x`, `y` would do to the part
+    /// numbers and gears, found from its neighbours alone. `None` if no
+    /// symbol covers that cell.
+    pub fn impact_of_removing(&self, x: usize, y: usize) -> Option<RemovalImpact> {
+        let index = self.grid.get(&self.vertices, x, y)?;
+        let vertex = &self.vertices[index];
+        let symbol = SymbolRef::new(vertex)?;
+        let lost_parts: Vec<Vertex> = self.adjacency[index]
+            .iter()
+            .map(|id| *id as usize)
+            .filter(|other| self.vertices[*other].as_number().is_some())
+            .filter(|other| {
+                // Only numbers with no other symbol next to them are lost.
+                !self.adjacency[*other]
+                    .iter()
+                    .map(|id| *id as usize)
+                    .any(|symbol| symbol != index && self.vertices[symbol].as_symbol().is_some())
+            })
+            .map(|other| self.vertices[other])
+            .collect();
+        let lost_gear = Gear::new(vertex, self.adjacent(vertex), GEAR_SYMBOL, GEAR_ARITY);
+        let part_number_sum_delta = -lost_parts.iter().filter_map(Vertex::as_number).map(i128::from).sum::<i128>();
+        let gear_ratio_sum_delta = match &lost_gear {
+            Some(gear) => gear.ratio.map(|ratio| -i128::from(ratio)),
+            None => Some(0),
+        };
+        Some(RemovalImpact { symbol, lost_parts, lost_gear, part_number_sum_delta, gear_ratio_sum_delta })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+
+    #[test]
+    fn test_impact_of_removing() {
+        let schematic = Schematic::parse(include_str!("../input/sample.txt"));
+        let impact = schematic.impact_of_removing(3, 1).unwrap();
+        assert_eq!(impact.symbol, SymbolRef { symbol: '*', position: Point::new(3, 1) });
+        assert_eq!(impact.lost_parts, vec![Vertex::number(467, 0, 0), Vertex::number(35, 2, 2)]);
+        assert_eq!(impact.lost_gear.unwrap().numbers, vec![467, 35]);
+        assert_eq!(impact.part_number_sum_delta, -(467 + 35));
+        assert_eq!(impact.gear_ratio_sum_delta, Some(-16345));
+        assert!(schematic.impact_of_removing(0, 0).is_none());
+        assert!(schematic.impact_of_removing(1, 1).is_none());
+
+        // Matches deleting the symbol and comparing.
+        for symbol in schematic.symbols() {
+            let impact = schematic.impact_of_removing(symbol.min_x, symbol.y).unwrap();
+            let mut after = schematic.clone();
+            after.set_cell(symbol.min_x, symbol.y, '.');
+            let diff = schematic.diff(&after);
+            assert_eq!(Some(impact.part_number_sum_delta), diff.part_number_sum_delta);
+            assert_eq!(impact.gear_ratio_sum_delta, diff.gear_ratio_sum_delta);
+        }
+
+        // A number next to two symbols survives losing either.
+        let shared = Schematic::parse("#12*");
+        assert!(shared.impact_of_removing(0, 0).unwrap().lost_parts.is_empty());
+    }
+}
@@ -1,100 +1,818 @@
-use regex::Regex;
-use std::collections::{HashSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::ops::Range;
+use std::path::Path;
 
+mod borrowed;
+mod diff;
+mod grid;
+mod impact;
+mod options;
+mod reduce;
+mod render;
+mod stream;
+mod transform;
+mod vertical;
 mod vertex;
 
-use vertex::{Data, Vertex};
-
-pub fn part_numbers(input: Vec<String>) -> Vec<u32> {
-    build_adjacency_list(input)
-        .iter()
-        .filter_map(|(vertex, adjacents)| {
-            // find numbers adjacent to symbols
-            match vertex.data {
-                Data::Number(n) => {
-                    if adjacents.iter().any(|v| matches!(v.data, Data::Symbol(_))) {
-                        Some(n)
-                    } else {
-                        None
-                    }
-                },
-                Data::Symbol(_s) => None
+pub use borrowed::{BorrowedSchematic, Span};
+pub use diff::{SchematicDiff, VertexChange};
+pub use impact::RemovalImpact;
+pub use options::{Adjacency, Alphabet, InvalidRadix, RaggedLines, SchematicOptions, Storage};
+pub use reduce::{Reduce, Reduction, SymbolReduction};
+pub use render::RenderFormat;
+pub use stream::{Finalized, Stream};
+pub use vertical::VerticalNumber;
+
+use grid::{Grid, VertexId};
+pub use vertex::{Data, Point, Rect, SignedPoint, Vertex};
+
+/// A number adjacent to at least one symbol, with where it is.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PartNumber {
+    pub value: u64,
+    pub y: usize,
+    pub min_x: usize,
+    pub max_x: usize,
+    /// The symbols it touches, in reading order.
+    pub adjacent_symbols: Vec<SymbolRef>,
+}
+
+/// A symbol and where it is.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SymbolRef {
+    pub symbol: char,
+    pub position: Point,
+}
+
+/// The puzzle's gear: a `*` next to exactly two numbers.
+pub const GEAR_SYMBOL: char = '*';
+pub const GEAR_ARITY: usize = 2;
+
+/// A symbol next to a given number of numbers, from `Schematic::gears`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gear {
+    pub symbol: char,
+    pub position: Point,
+    /// The adjacent numbers, in reading order.
+    pub numbers: Vec<u64>,
+    /// The product of `numbers`, or `None` if it doesn't fit in a `u64`.
+    pub ratio: Option<u64>,
+}
+
+/// Numbers and symbols connected to each other through adjacency, from
+/// `Schematic::components`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Component {
+    /// Its numbers and symbols, in reading order.
+    pub vertices: Vec<Vertex>,
+    /// The sum of its numbers, or `None` if it doesn't fit in a `u64`.
+    pub sum: Option<u64>,
+}
+
+/// How often one symbol appears and how many numbers it touches, from
+/// `Schematic::symbol_stats`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SymbolStats {
+    pub count: usize,
+    /// How many of them touch at least one number.
+    pub touching_numbers: usize,
+    /// How many of them touch each number of numbers.
+    pub by_adjacent_numbers: BTreeMap<usize, usize>,
+}
+
+impl PartNumber {
+    /// `vertex` as a part number, if it's a number and a symbol is among its
+    /// `neighbours`.
+    fn new<'a>(vertex: &Vertex, neighbours: impl Iterator<Item = &'a Vertex>) -> Option<Self> {
+        let adjacent_symbols: Vec<SymbolRef> = neighbours.filter_map(SymbolRef::new).collect();
+        if adjacent_symbols.is_empty() {
+            return None;
+        }
+        Some(PartNumber {
+            value: vertex.as_number()?,
+            y: vertex.y,
+            min_x: vertex.min_x,
+            max_x: vertex.max_x,
+            adjacent_symbols,
+        })
+    }
+
+    /// Which characters the symbols it touches are, each once.
+    pub fn symbol_chars(&self) -> BTreeSet<char> {
+        self.adjacent_symbols.iter().map(|symbol| symbol.symbol).collect()
+    }
+}
+
+impl SymbolRef {
+    fn new(vertex: &Vertex) -> Option<Self> {
+        Some(SymbolRef { symbol: vertex.as_symbol()?, position: Point::new(vertex.min_x, vertex.y) })
+    }
+}
+
+impl Gear {
+    /// `vertex` as a gear, if it's `symbol` and exactly `arity` of its
+    /// `neighbours` are numbers.
+    fn new<'a>(
+        vertex: &Vertex,
+        neighbours: impl Iterator<Item = &'a Vertex>,
+        symbol: char,
+        arity: usize,
+    ) -> Option<Self> {
+        if vertex.as_symbol() != Some(symbol) {
+            return None;
+        }
+        let numbers: Vec<u64> = neighbours.filter_map(|v| v.as_number()).collect();
+        if numbers.len() != arity {
+            return None;
+        }
+        Some(Gear {
+            symbol,
+            position: Point::new(vertex.min_x, vertex.y),
+            ratio: numbers.iter().try_fold(1u64, |product, n| product.checked_mul(*n)),
+            numbers,
+        })
+    }
+}
+
+/// An engine schematic, parsed once so it can be queried repeatedly. With
+/// the `serde` feature it can be saved and loaded without parsing it again.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "SavedSchematic"))]
+pub struct Schematic {
+    /// Every number and symbol, in reading order.
+    vertices: Vec<Vertex>,
+    /// The indices in `vertices` of each vertex's neighbours.
+    adjacency: Vec<Vec<VertexId>>,
+    /// The schematic's lines, kept so `set_cell` can re-read one.
+    lines: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    grid: Grid,
+    options: SchematicOptions,
+    /// How ragged lines were dealt with, if there were any.
+    ragged: Option<RaggedLines>,
+}
+
+/// A saved `Schematic`, without what's quicker to rebuild than to load.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SavedSchematic {
+    vertices: Vec<Vertex>,
+    adjacency: Vec<Vec<VertexId>>,
+    lines: Vec<String>,
+    options: SchematicOptions,
+    #[serde(default)]
+    ragged: Option<RaggedLines>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SavedSchematic> for Schematic {
+    type Error = String;
+
+    fn try_from(saved: SavedSchematic) -> Result<Self, String> {
+        let SavedSchematic { vertices, adjacency, lines, options, ragged } = saved;
+        if adjacency.len() != vertices.len() || adjacency.iter().flatten().any(|id| *id as usize >= vertices.len()) {
+            return Err("adjacency doesn't match the vertices".to_string());
+        }
+        let widths: Vec<usize> = lines.iter().map(|line| cell_offsets(line).len()).collect();
+        if let Some(vertex) = vertices.iter().find(|vertex| widths.get(vertex.y).is_none_or(|width| vertex.max_x >= *width)) {
+            return Err(format!("{vertex:?} is off the grid"));
+        }
+        if vertices.windows(2).any(|pair| (pair[0].y, pair[0].max_x) >= (pair[1].y, pair[1].min_x)) {
+            return Err("the vertices overlap or are out of order".to_string());
+        }
+        let grid = Grid::new(options.storage, widths, &vertices);
+        Ok(Self { vertices, adjacency, lines, grid, options, ragged })
+    }
+}
+
+/// Why `Schematic::try_parse` rejected its input. Lines are numbered from 1;
+/// widths and columns are in cells, as for `Vertex`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SchematicError {
+    /// There are no lines, or the first line is blank.
+    Empty,
+    /// A line isn't as wide as the first.
+    Ragged { line: usize, expected: usize, found: usize },
+    /// A tab or other control character.
+    ControlCharacter { line: usize, column: usize, found: char },
+    /// A run of digits too large for a `u64`.
+    NumberTooLarge { line: usize, column: usize, digits: String },
+}
+
+impl fmt::Display for SchematicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchematicError::Empty => write!(f, "the schematic is empty"),
+            SchematicError::Ragged { line, expected, found } => {
+                write!(f, "line {line} is {found} wide but line 1 is {expected}")
+            },
+            SchematicError::ControlCharacter { line, column, found } => {
+                write!(f, "control character {found:?} at line {line}, column {column}")
+            },
+            SchematicError::NumberTooLarge { line, column, digits } => {
+                write!(f, "number {digits} at line {line}, column {column} is too large")
+            },
+        }
+    }
+}
+
+impl Error for SchematicError {}
+
+/// A sum, or a gear ratio going into one, didn't fit in a `u64`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OverflowError;
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the sum is too large for a u64")
+    }
+}
+
+impl Error for OverflowError {}
+
+impl Schematic {
+    pub fn parse(input: &str) -> Self {
+        Self::from_lines(input.lines())
+    }
+
+    pub fn parse_with(input: &str, options: &SchematicOptions) -> Self {
+        Self::from_lines_with(input.lines(), options)
+    }
+
+    /// Like `parse`, but rejects input that would otherwise be read in a
+    /// surprising way: an empty or non-rectangular grid, control characters,
+    /// or numbers too large to hold.
+    pub fn try_parse(input: &str) -> Result<Self, SchematicError> {
+        Self::try_parse_with(input, &SchematicOptions { ragged: RaggedLines::Reject, ..Default::default() })
+    }
+
+    /// Like `parse_with`, but rejects an empty grid, control characters and
+    /// numbers too large to hold, and ragged lines if `options` say to.
+    pub fn try_parse_with(input: &str, options: &SchematicOptions) -> Result<Self, SchematicError> {
+        let alphabet = &options.alphabet;
+        let is_digit = |c: char| alphabet.is_digit(c) && !alphabet.is_blank(c);
+        let width = input.lines().next().map_or(0, |line| cell_offsets(line).len());
+        if width == 0 {
+            return Err(SchematicError::Empty);
+        }
+        for (y, line) in input.lines().enumerate() {
+            let line_no = y + 1;
+            let offsets = cell_offsets(line);
+            if options.ragged == RaggedLines::Reject && offsets.len() != width {
+                return Err(SchematicError::Ragged { line: line_no, expected: width, found: offsets.len() });
+            }
+            let column = |byte: usize| offsets.partition_point(|offset| *offset <= byte) - 1;
+            if let Some((byte, found)) = line.char_indices().find(|(_, c)| c.is_control()) {
+                return Err(SchematicError::ControlCharacter { line: line_no, column: column(byte), found });
             }
+            let mut digits = line.char_indices().peekable();
+            while let Some((start, c)) = digits.next() {
+                if !is_digit(c) {
+                    continue;
+                }
+                let mut end = start + 1;
+                while let Some((x, _)) = digits.next_if(|(_, c)| is_digit(*c)) {
+                    end = x + 1;
+                }
+                if u64::from_str_radix(&line[start..end], alphabet.radix()).is_err() {
+                    let digits = line[start..end].to_string();
+                    return Err(SchematicError::NumberTooLarge { line: line_no, column: column(start), digits });
+                }
+            }
+        }
+        Ok(Self::parse_with(input, options))
+    }
+
+    pub fn from_lines<I, S>(lines: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self::from_lines_with(lines, &SchematicOptions::default())
+    }
+
+    pub fn from_lines_with<I, S>(lines: I, options: &SchematicOptions) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let lines = lines.into_iter().map(|line| line.as_ref().to_string()).collect();
+        Self::from_owned_lines(lines, options)
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        Self::from_reader_with(reader, &SchematicOptions::default())
+    }
+
+    pub fn from_reader_with<R: BufRead>(reader: R, options: &SchematicOptions) -> io::Result<Self> {
+        let lines = reader.lines().collect::<io::Result<_>>()?;
+        Ok(Self::from_owned_lines(lines, options))
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_path_with(path, &SchematicOptions::default())
+    }
+
+    pub fn from_path_with<P: AsRef<Path>>(path: P, options: &SchematicOptions) -> io::Result<Self> {
+        Self::from_reader_with(BufReader::new(File::open(path)?), options)
+    }
+
+    /// Reads a grid already split into rows of characters.
+    pub fn from_grid(grid: &[Vec<char>]) -> Self {
+        Self::from_grid_with(grid, &SchematicOptions::default())
+    }
+
+    pub fn from_grid_with(grid: &[Vec<char>], options: &SchematicOptions) -> Self {
+        let lines = grid.iter().map(|row| row.iter().collect()).collect();
+        Self::from_owned_lines(lines, options)
+    }
+
+    fn from_owned_lines(mut lines: Vec<String>, options: &SchematicOptions) -> Self {
+        let ragged = even_out(&mut lines, options);
+        let mut vertices: Vec<Vertex> = vec![];
+        let mut widths: Vec<usize> = vec![];
+        for (y, line) in lines.iter().enumerate() {
+            widths.push(cell_offsets(line).len());
+            vertices.extend(analyze_line(line, y, options));
+        }
+        let grid = Grid::new(options.storage, widths, &vertices);
+        let adjacency = build_adjacency_list(&vertices, &grid, options);
+        Self { vertices, adjacency, lines, grid, options: options.clone(), ragged }
+    }
+
+    /// How ragged lines were dealt with, or `None` if every line was as
+    /// wide as the others.
+    pub fn ragged(&self) -> Option<RaggedLines> {
+        self.ragged
+    }
+
+    /// Replaces the cell at column `x` of line `y` with `c`. Only line
+    /// `y` is read again, and only the numbers and symbols near it have their
+    /// neighbours found again, so an edit costs far less than a reparse.
+    ///
+    /// Panics if there's no cell at `x`, `y`.
+    pub fn set_cell(&mut self, x: usize, y: usize, c: char) {
+        let old = cell_range(&self.lines[y], x).expect("no cell at x, y");
+        let old_width = self.width();
+        self.lines[y].replace_range(old, c.encode_utf8(&mut [0; 4]));
+
+        // Swap line `y`'s vertices for the new ones, renumbering those after.
+        let old = self.grid.row(y).expect("no line y");
+        let fresh = analyze_line(&self.lines[y], y, &self.options);
+        let fresh_end = old.start + fresh.len();
+        let renumber = |id: VertexId| {
+            let index = id as usize;
+            (if index >= old.end { index + fresh_end - old.end } else { index }) as VertexId
+        };
+        self.vertices.splice(old.clone(), fresh);
+        self.adjacency.splice(old.clone(), vec![vec![]; fresh_end - old.start]);
+        for neighbours in &mut self.adjacency {
+            neighbours.iter_mut().for_each(|id| *id = renumber(*id));
+        }
+        let width = cell_offsets(&self.lines[y]).len();
+        self.grid.replace_row(y, width, old, fresh_end, &self.vertices);
+
+        // Only lines within reach of line `y` can have gained or lost a
+        // neighbour, unless the grid wraps and its width changed.
+        let r = self.options.adjacency.radius();
+        let height = self.grid.height();
+        let rows: Vec<usize> = if self.options.wrap && self.width() != old_width {
+            (0..height).collect()
+        } else if self.options.wrap {
+            (0..=2 * r).map(|dy| (y + height * (r / height + 1) + dy - r) % height).collect()
+        } else {
+            (y.saturating_sub(r)..=(y + r).min(height - 1)).collect()
+        };
+        let wrap = self.options.wrap.then(|| (self.width(), height));
+        for row in rows {
+            for index in self.grid.row(row).unwrap_or_default() {
+                let (grid, vertices) = (&self.grid, &self.vertices);
+                self.adjacency[index] =
+                    neighbours(&vertices[index], self.options.adjacency, wrap, |x, y| grid.id(vertices, x, y));
+            }
+        }
+    }
+
+    /// The widest line's width.
+    fn width(&self) -> usize {
+        self.grid.width()
+    }
+
+    /// The options the schematic was parsed with.
+    pub fn options(&self) -> &SchematicOptions {
+        &self.options
+    }
+
+    /// The numbers and symbols next to `vertex`, in reading order.
+    pub fn adjacent(&self, vertex: &Vertex) -> impl Iterator<Item = &Vertex> {
+        self.grid
+            .get(&self.vertices, vertex.min_x, vertex.y)
+            .filter(|index| self.vertices[*index] == *vertex)
+            .into_iter()
+            .flat_map(|index| self.adjacency[index].iter().map(|other| &self.vertices[*other as usize]))
+    }
+
+    /// The characters `vertex` covers, such as the whole of a multi-character
+    /// token.
+    pub fn text(&self, vertex: &Vertex) -> Option<&str> {
+        let line = self.lines.get(vertex.y)?;
+        let start = cell_range(line, vertex.min_x)?.start;
+        let end = cell_range(line, vertex.max_x)?.end;
+        line.get(start..end)
+    }
+
+    /// The number or symbol covering the cell at `x`, `y`, if any.
+    pub fn at(&self, x: usize, y: usize) -> Option<&Vertex> {
+        let index = self.grid.get(&self.vertices, x, y)?;
+        Some(&self.vertices[index])
+    }
+
+    /// The numbers and symbols touching the cell at `x`, `y`, in reading
+    /// order, leaving out whatever covers the cell itself.
+    pub fn adjacent_to_point(&self, x: usize, y: usize) -> Vec<&Vertex> {
+        let own = self.grid.get(&self.vertices, x, y);
+        let wrap = self.options.wrap.then(|| (self.width(), self.grid.height()));
+        let cell = Vertex::symbol('.', y, x);
+        neighbours(&cell, self.options.adjacency, wrap, |x, y| {
+            self.grid.get(&self.vertices, x, y).filter(|index| Some(*index) != own)
+        })
+        .into_iter()
+        .map(|index| &self.vertices[index])
+        .collect()
+    }
+
+    /// Writes the adjacency graph in Graphviz's DOT language. Numbers are
+    /// boxes, symbols are circles, and each node is named after its position.
+    pub fn to_dot<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "graph schematic {{")?;
+        for vertex in &self.vertices {
+            let (label, shape) = match vertex.data {
+                Data::Number(n) => (n.to_string(), "box"),
+                Data::Symbol(s) => (s.to_string(), "circle"),
+                Data::Overflow => ("?".to_string(), "box"),
+            };
+            writeln!(writer, "  {} [label={label:?}, shape={shape}];", dot_id(vertex))?;
+        }
+        for (index, neighbours) in self.adjacency.iter().enumerate() {
+            for other in neighbours.iter().map(|other| *other as usize).filter(|other| *other > index) {
+                writeln!(writer, "  {} -- {};", dot_id(&self.vertices[index]), dot_id(&self.vertices[other]))?;
+            }
+        }
+        writeln!(writer, "}}")
+    }
+
+    /// The adjacency graph, with each number or symbol as a node.
+    #[cfg(feature = "petgraph")]
+    pub fn to_graph(&self) -> petgraph::graph::UnGraph<Vertex, ()> {
+        let mut graph = petgraph::graph::UnGraph::with_capacity(self.vertices.len(), self.vertices.len());
+        let nodes: Vec<_> = self.vertices.iter().map(|vertex| graph.add_node(*vertex)).collect();
+        for (index, neighbours) in self.adjacency.iter().enumerate() {
+            for other in neighbours.iter().map(|other| *other as usize).filter(|other| *other > index) {
+                graph.add_edge(nodes[index], nodes[other], ());
+            }
+        }
+        graph
+    }
+
+    /// Every number and symbol, with the cells it covers, in reading order.
+    pub fn vertices(&self) -> impl Iterator<Item = &Vertex> {
+        self.vertices.iter()
+    }
+
+    /// The numbers among `vertices`, in reading order.
+    pub fn numbers(&self) -> impl Iterator<Item = &Vertex> {
+        self.vertices().filter(|v| matches!(v.data, Data::Number(_)))
+    }
+
+    /// The symbols among `vertices`, in reading order.
+    pub fn symbols(&self) -> impl Iterator<Item = &Vertex> {
+        self.vertices().filter(|v| matches!(v.data, Data::Symbol(_)))
+    }
+
+    /// The numbers covering any cell of `rect`, in reading order.
+    pub fn numbers_in(&self, rect: Rect) -> impl Iterator<Item = &Vertex> {
+        self.vertices_in(rect).filter(|v| matches!(v.data, Data::Number(_)))
+    }
+
+    /// The symbols covering any cell of `rect`, in reading order.
+    pub fn symbols_in(&self, rect: Rect) -> impl Iterator<Item = &Vertex> {
+        self.vertices_in(rect).filter(|v| matches!(v.data, Data::Symbol(_)))
+    }
+
+    /// Each row's vertices are in order and don't overlap, so those in
+    /// `rect` are found by binary search.
+    fn vertices_in(&self, rect: Rect) -> impl Iterator<Item = &Vertex> {
+        let max_y = rect.max.y.min(self.grid.height());
+        (rect.min.y..=max_y).flat_map(move |y| {
+            let row = &self.vertices[self.grid.row(y).unwrap_or_default()];
+            let start = row.partition_point(|v| v.max_x < rect.min.x);
+            row[start..].iter().take_while(move |v| v.min_x <= rect.max.x)
         })
-        .collect::<Vec<u32>>()
-}
-
-pub fn gear_ratios(input: Vec<String>) -> Vec<u32> {
-    build_adjacency_list(input)
-        .iter()
-        .filter_map(|(vertex, adjacents)| {
-            match vertex.data {
-                Data::Number(_n) => None,
-                Data::Symbol(_s) => {
-                    let iter = adjacents.iter();
-                    if iter.len() == 2 && iter.clone().all(|v| matches!(v.data, Data::Number(_))) {
-                        let gear_ratio = iter.map(|n| {
-                            if let Data::Number(num) = n.data { num } else { 0 }
-                        }).product::<u32>();
-                        Some(gear_ratio)
-                    } else {
-                        None
+    }
+
+    /// Numbers adjacent to a symbol, in reading order.
+    pub fn part_numbers(&self) -> Vec<u64> {
+        self.parts().into_iter().map(|part| part.value).collect()
+    }
+
+    /// Like `part_numbers`, but with each number's position and the symbols
+    /// that make it a part number.
+    pub fn parts(&self) -> Vec<PartNumber> {
+        self.numbers()
+            .filter_map(|vertex| PartNumber::new(vertex, self.adjacent(vertex)))
+            .collect()
+    }
+
+    /// The numbers not adjacent to any symbol, which `part_numbers` leaves
+    /// out, in reading order.
+    pub fn orphans(&self) -> impl Iterator<Item = &Vertex> {
+        self.numbers().filter(|vertex| self.is_orphan(vertex))
+    }
+
+    fn is_orphan(&self, vertex: &Vertex) -> bool {
+        !self.adjacent(vertex).any(|other| other.as_symbol().is_some())
+    }
+
+    /// Part numbers touching at least `min` symbols, such as those counted
+    /// for more than one reason when `min` is 2.
+    pub fn parts_touching(&self, min: usize) -> Vec<PartNumber> {
+        self.parts()
+            .into_iter()
+            .filter(|part| part.adjacent_symbols.len() >= min)
+            .collect()
+    }
+
+    /// The symbols that make `part` a part number in this schematic, in
+    /// reading order. Empty if `part` isn't one of this schematic's numbers.
+    pub fn symbols_for(&self, part: &PartNumber) -> Vec<SymbolRef> {
+        let vertex = Vertex { data: Data::Number(part.value), y: part.y, min_x: part.min_x, max_x: part.max_x };
+        self.adjacent(&vertex).filter_map(SymbolRef::new).collect()
+    }
+
+    /// Numbers adjacent to a `symbol`, in reading order.
+    pub fn numbers_adjacent_to_symbol(&self, symbol: char) -> Vec<u64> {
+        self.numbers_adjacent_to_any_of(&[symbol])
+    }
+
+    /// Numbers adjacent to at least one of `symbols`, in reading order.
+    pub fn numbers_adjacent_to_any_of(&self, symbols: &[char]) -> Vec<u64> {
+        self.parts()
+            .into_iter()
+            .filter(|part| part.adjacent_symbols.iter().any(|symbol| symbols.contains(&symbol.symbol)))
+            .map(|part| part.value)
+            .collect()
+    }
+
+    /// Every `symbol` next to exactly `arity` numbers, in reading order.
+    pub fn gears(&self, symbol: char, arity: usize) -> Vec<Gear> {
+        self.symbols()
+            .filter_map(|vertex| Gear::new(vertex, self.adjacent(vertex), symbol, arity))
+            .collect()
+    }
+
+    /// The connected clusters of numbers and symbols, in reading order of
+    /// their first vertex. A lone number or symbol is a cluster of its own.
+    pub fn components(&self) -> Vec<Component> {
+        let mut seen = vec![false; self.vertices.len()];
+        let mut components = vec![];
+        for first in 0..self.vertices.len() {
+            if seen[first] {
+                continue;
+            }
+            seen[first] = true;
+            let mut members = vec![first];
+            let mut next = 0;
+            while let Some(index) = members.get(next).copied() {
+                next += 1;
+                for other in &self.adjacency[index] {
+                    let other = *other as usize;
+                    if !seen[other] {
+                        seen[other] = true;
+                        members.push(other);
                     }
                 }
             }
-        })
-        .collect::<Vec<u32>>()
-}
-
-fn build_adjacency_list(input: Vec<String>) -> HashMap<Vertex, HashSet<Vertex>> {
-    let mut verts_by_line_no: HashMap<usize, Vec<Vertex>> = HashMap::new();
-    let mut adj_list: HashMap<Vertex, HashSet<Vertex>> = HashMap::new();
-    input
-        .iter()
-        .enumerate()
-        .for_each(|(y, line)| {
-            let analysis = analyze_line(line, y);
-            let iter = analysis.iter();
-            let mut with_offset = iter.clone();
-            with_offset.next();
-            for (a, b) in iter.zip(with_offset) {
-                if a.is_adjacent_to(b) {
-                    adj_list.entry(*a).or_default().insert(*b);
-                    adj_list.entry(*b).or_default().insert(*a);
+            members.sort_unstable();
+            let vertices: Vec<Vertex> = members.into_iter().map(|index| self.vertices[index]).collect();
+            let sum = vertices
+                .iter()
+                .filter_map(Vertex::as_number)
+                .try_fold(0u64, u64::checked_add);
+            components.push(Component { vertices, sum });
+        }
+        components
+    }
+
+    /// A summary of each symbol in the schematic, keyed by the symbol.
+    pub fn symbol_stats(&self) -> BTreeMap<char, SymbolStats> {
+        let mut stats: BTreeMap<char, SymbolStats> = BTreeMap::new();
+        for vertex in self.symbols() {
+            let Some(symbol) = vertex.as_symbol() else { continue };
+            let numbers = self.adjacent(vertex).filter(|v| v.as_number().is_some()).count();
+            let entry = stats.entry(symbol).or_default();
+            entry.count += 1;
+            if numbers > 0 {
+                entry.touching_numbers += 1;
+            }
+            *entry.by_adjacent_numbers.entry(numbers).or_default() += 1;
+        }
+        stats
+    }
+
+    /// The ratio of each of the puzzle's gears. A ratio too large for a `u64`
+    /// saturates at `u64::MAX`.
+    pub fn gear_ratios(&self) -> Vec<u64> {
+        self.gears(GEAR_SYMBOL, GEAR_ARITY)
+            .into_iter()
+            .map(|gear| gear.ratio.unwrap_or(u64::MAX))
+            .collect()
+    }
+
+    /// The sum of the part numbers.
+    pub fn part_number_sum(&self) -> Result<u64, OverflowError> {
+        self.part_numbers()
+            .into_iter()
+            .try_fold(0u64, u64::checked_add)
+            .ok_or(OverflowError)
+    }
+
+    /// The sum of the puzzle's gear ratios. It's an error if a ratio or the
+    /// sum overflows.
+    pub fn gear_ratio_sum(&self) -> Result<u64, OverflowError> {
+        self.gears(GEAR_SYMBOL, GEAR_ARITY)
+            .into_iter()
+            .try_fold(0u64, |sum, gear| sum.checked_add(gear.ratio?))
+            .ok_or(OverflowError)
+    }
+}
+
+pub fn part_numbers<I, S>(input: I) -> Vec<u64>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    Schematic::from_lines(input).part_numbers()
+}
+
+pub fn gear_ratios<I, S>(input: I) -> Vec<u64>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    Schematic::from_lines(input).gear_ratios()
+}
+
+/// Pads or truncates `lines` as `options` say to, if they aren't all as
+/// wide, and returns what was done.
+fn even_out(lines: &mut [String], options: &SchematicOptions) -> Option<RaggedLines> {
+    let widths: Vec<usize> = lines.iter().map(|line| cell_offsets(line).len()).collect();
+    let (min, max) = (widths.iter().min()?, widths.iter().max()?);
+    if min == max {
+        return None;
+    }
+    match options.ragged {
+        RaggedLines::Keep | RaggedLines::Reject => {},
+        RaggedLines::Pad => {
+            let blank = options.alphabet.blanks.first().copied().unwrap_or('.');
+            for (line, width) in lines.iter_mut().zip(&widths) {
+                line.extend(std::iter::repeat_n(blank, max - width));
+            }
+        },
+        RaggedLines::Truncate => {
+            for line in lines.iter_mut() {
+                if let Some(cut) = cell_range(line, *min) {
+                    line.truncate(cut.start);
                 }
             }
-            if y > 0 {
-                let last_verts = verts_by_line_no.get(&(y - 1)).unwrap();
-                analysis
-                    .iter()
-                    .for_each(|v| {
-                        last_verts.iter().for_each(|lv| {
-                            if v.is_adjacent_to(lv) {
-                                adj_list.entry(*lv).or_default().insert(*v);
-                                adj_list.entry(*v).or_default().insert(*lv);
-                            }
-                        })
-                    });
-            }
-            verts_by_line_no.insert(y, analysis);
-        });
-        adj_list
+        },
+    }
+    Some(options.ragged)
+}
+
+fn dot_id(vertex: &Vertex) -> String {
+    format!("v{}_{}", vertex.y, vertex.min_x)
+}
+
+/// Each vertex's neighbours, by index, from a grid of which vertex covers
+/// each cell. The grid is only read, so with the `parallel` feature the
+/// vertices are shared out between threads without any stitching at the
+/// edges of each thread's share.
+fn build_adjacency_list(vertices: &[Vertex], grid: &Grid, options: &SchematicOptions) -> Vec<Vec<VertexId>> {
+    #[cfg(feature = "parallel")]
+    use rayon::prelude::*;
+    let wrap = options.wrap.then(|| (grid.width(), grid.height()));
+    #[cfg(feature = "parallel")]
+    let each = vertices.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let each = vertices.iter();
+    each.map(|vertex| neighbours(vertex, options.adjacency, wrap, |x, y| grid.id(vertices, x, y)))
+        .collect()
 }
 
-fn analyze_line(line: &str, y: usize) -> Vec<Vertex> {
+/// What occupies the cells touching `vertex`, as found by `cell`, in reading
+/// order and without repeats. With `wrap` set to the grid's width and height,
+/// cells off one edge are found on the opposite edge.
+pub(crate) fn neighbours<T, F>(vertex: &Vertex, adjacency: Adjacency, wrap: Option<(usize, usize)>, cell: F) -> Vec<T>
+where
+    T: PartialEq,
+    F: Fn(usize, usize) -> Option<T>,
+{
+    let r = adjacency.radius() as isize;
+    let (y, min_x, max_x) = (vertex.y as isize, vertex.min_x as isize, vertex.max_x as isize);
+    let mut points: Vec<Point> = vec![];
+    for dy in -r..=r {
+        for x in min_x - r..=max_x + r {
+            if (dy == 0 && (min_x..=max_x).contains(&x)) || !adjacency.reaches(vertex, x, y + dy) {
+                continue;
+            }
+            let Some(point) = SignedPoint::new(x, y + dy).wrapped(wrap) else { continue };
+            // On a narrow enough torus a vertex can wrap around onto itself.
+            if point.y == vertex.y && vertex.span().contains(&point.x) {
+                continue;
+            }
+            points.push(point);
+        }
+    }
+    if wrap.is_some() {
+        points.sort_by_key(|point| (point.y, point.x));
+    }
+    let mut neighbours: Vec<T> = vec![];
+    for point in points {
+        if let Some(other) = cell(point.x, point.y) {
+            if !neighbours.contains(&other) {
+                neighbours.push(other);
+            }
+        }
+    }
+    neighbours
+}
+
+/// Where each of `line`'s cells starts, in bytes. A cell is a character,
+/// or with the `graphemes` feature a grapheme cluster, so a column is a
+/// count of cells rather than of bytes.
+pub(crate) fn cell_offsets(line: &str) -> Vec<usize> {
+    #[cfg(feature = "graphemes")]
+    use unicode_segmentation::UnicodeSegmentation;
+    #[cfg(feature = "graphemes")]
+    let cells = line.grapheme_indices(true);
+    #[cfg(not(feature = "graphemes"))]
+    let cells = line.char_indices();
+    cells.map(|(offset, _)| offset).collect()
+}
+
+/// The bytes of `line` in the cell at column `x`.
+fn cell_range(line: &str, x: usize) -> Option<Range<usize>> {
+    let offsets = cell_offsets(line);
+    let start = *offsets.get(x)?;
+    Some(start..offsets.get(x + 1).copied().unwrap_or(line.len()))
+}
+
+fn analyze_line(line: &str, y: usize, options: &SchematicOptions) -> Vec<Vertex> {
+    let alphabet = &options.alphabet;
+    let offsets = cell_offsets(line);
+    let cell = |x: usize| &line[offsets[x]..offsets.get(x + 1).copied().unwrap_or(line.len())];
+    let first = |x: usize| cell(x).chars().next().unwrap_or('.');
+    let is_digit = |x: usize| cell(x).len() == 1 && alphabet.is_digit(first(x)) && !alphabet.is_blank(first(x));
     let mut analysis = vec![];
-    let reg = Regex::new(r"(\d+)|([^.])").unwrap();
-    reg.find_iter(line)
-        .for_each(|m| {
-            if let Ok(number) = m.as_str().parse() {
-                let n = Vertex::number(number, y, m.start());
-                analysis.push(n);
-            } else {
-                let symbol = m.as_str().chars().next().unwrap();
-                let s = Vertex::symbol(symbol, y, m.start());
-                analysis.push(s);
+    let mut x = 0;
+    while x < offsets.len() {
+        let min_x = x;
+        let c = first(x);
+        let token = options
+            .tokens
+            .iter()
+            .filter(|token| !token.is_empty() && line[offsets[x]..].starts_with(token.as_str()))
+            .max_by_key(|token| token.len());
+        if let Some(token) = token {
+            let end = offsets[x] + token.len();
+            while offsets.get(x + 1).is_some_and(|offset| *offset < end) {
+                x += 1;
             }
-        });
+            analysis.push(Vertex::token(c, y, min_x, x));
+        } else if is_digit(x) {
+            while x + 1 < offsets.len() && is_digit(x + 1) {
+                x += 1;
+            }
+            // Digits are a byte each.
+            match u64::from_str_radix(&line[offsets[min_x]..=offsets[x]], alphabet.radix()) {
+                Ok(number) => analysis.push(Vertex { data: Data::Number(number), y, min_x, max_x: x }),
+                Err(_) => analysis.push(Vertex { data: Data::Overflow, y, min_x, max_x: x }),
+            }
+        } else if !alphabet.is_blank(c) && alphabet.is_symbol(c) {
+            analysis.push(Vertex::symbol(c, y, min_x));
+        }
+        x += 1;
+    }
     analysis
 }
 
@@ -111,7 +829,7 @@ mod tests {
     where P: AsRef<Path> {
         if let Ok(lines) = lines(filename) {
             lines
-                .flatten()
+                .map_while(Result::ok)
                 .collect()
         } else {
             vec![]
@@ -129,7 +847,7 @@ mod tests {
     fn test_sum_part_numbers_from_sample() {
         let sample_input = read_input("input/sample.txt");
         let part_numbers = part_numbers(sample_input);
-        let sum: u32 = part_numbers
+        let sum: u64 = part_numbers
             .iter()
             .sum();
 
@@ -140,7 +858,7 @@ mod tests {
     fn test_sum_part_numbers_from_input() {
         let sample_input = read_input("input/input.txt");
         let part_numbers = part_numbers(sample_input);
-        let sum: u32 = part_numbers
+        let sum: u64 = part_numbers
             .iter()
             .sum();
 
@@ -151,7 +869,7 @@ mod tests {
     fn test_multiple_adjacencies_in_one_line() {
         let sample_input = vec!["..99*.99*".to_string()];
         let part_numbers = part_numbers(sample_input);
-        let sum: u32 = part_numbers.iter().sum();
+        let sum: u64 = part_numbers.iter().sum();
         assert_eq!(sum, 198);
     }
 
@@ -159,13 +877,13 @@ mod tests {
     fn test_multiple_runon_adjacencies_in_one_line() {
         let sample_input = vec!["..99*/99.".to_string()];
         let part_numbers = part_numbers(sample_input);
-        let sum: u32 = part_numbers.iter().sum();
+        let sum: u64 = part_numbers.iter().sum();
         assert_eq!(sum, 198);
     }
 
     #[test]
     fn test_analyze_line() {
-        let analysis = analyze_line("..99**99..", 0);
+        let analysis = analyze_line("..99**99..", 0, &SchematicOptions::default());
         assert_eq!(analysis.len(), 4);
         let first_number = analysis.iter().find(|v| matches!(v.data, Data::Number(99)) && v.min_x == 2).unwrap();
         let second_number = analysis.iter().find(|v| matches!(v.data, Data::Number(99)) && v.min_x == 6).unwrap();
@@ -177,17 +895,532 @@ mod tests {
         assert!(second_number.is_adjacent_to(second_symbol));
     }
 
+    #[test]
+    fn test_schematic_queries() {
+        let schematic = Schematic::parse(include_str!("../input/sample.txt"));
+        assert_eq!(schematic.part_numbers(), vec![467, 35, 633, 617, 592, 755, 664, 598]);
+        assert_eq!(schematic.gear_ratios(), vec![16345, 451490]);
+        assert_eq!(schematic.numbers().count(), 10);
+        let symbols: Vec<char> = schematic.symbols().filter_map(|v| v.as_symbol()).collect();
+        assert_eq!(symbols, vec!['*', '#', '*', '+', '$', '*']);
+        assert_eq!(schematic.vertices().count(), 16);
+        assert_eq!(schematic.vertices().nth(2), Some(&Vertex::symbol('*', 1, 3)));
+
+        let lone = schematic.numbers().find(|v| v.as_number() == Some(114)).unwrap();
+        assert_eq!(schematic.adjacent(lone).count(), 0);
+    }
+
+    #[test]
+    fn test_region_queries() {
+        let schematic = Schematic::parse(include_str!("../input/sample.txt"));
+        let rect = Rect::new(Point::new(2, 0), Point::new(5, 2));
+        let numbers: Vec<u64> = schematic.numbers_in(rect).filter_map(|v| v.as_number()).collect();
+        assert_eq!(numbers, vec![467, 114, 35]);
+        let symbols: Vec<&Vertex> = schematic.symbols_in(rect).collect();
+        assert_eq!(symbols, vec![&Vertex::symbol('*', 1, 3)]);
+
+        let everything = Rect::new(Point::new(0, 0), Point::new(usize::MAX, usize::MAX));
+        assert!(schematic.vertices_in(everything).eq(schematic.vertices()));
+        let empty = Rect::new(Point::new(0, 5), Point::new(1, 6));
+        assert_eq!(schematic.vertices_in(empty).count(), 0);
+        assert!(rect.contains(Point::new(5, 2)));
+        assert!(!rect.contains(Point::new(6, 2)));
+
+        let sparse = SchematicOptions { storage: Storage::Sparse, ..Default::default() };
+        let sparse = Schematic::parse_with(include_str!("../input/sample.txt"), &sparse);
+        assert!(sparse.numbers_in(rect).eq(schematic.numbers_in(rect)));
+    }
+
+    #[test]
+    fn test_parts_have_positions() {
+        let schematic = Schematic::parse("12.12\n..*..\n12#..");
+        assert_eq!(schematic.part_numbers(), vec![12, 12, 12]);
+        let parts = schematic.parts();
+        assert_eq!(parts[0], PartNumber {
+            value: 12,
+            y: 0,
+            min_x: 0,
+            max_x: 1,
+            adjacent_symbols: vec![SymbolRef { symbol: '*', position: Point::new(2, 1) }],
+        });
+        assert_eq!((parts[1].y, parts[1].min_x, parts[1].max_x), (0, 3, 4));
+        let symbols: Vec<char> = parts[2].adjacent_symbols.iter().map(|symbol| symbol.symbol).collect();
+        assert_eq!(symbols, vec!['*', '#']);
+        assert_eq!(parts[2].adjacent_symbols[1].position, Point::new(2, 2));
+    }
+
+    #[test]
+    fn test_orphans() {
+        let schematic = Schematic::parse(include_str!("../input/sample.txt"));
+        let orphans: Vec<&Vertex> = schematic.orphans().collect();
+        assert_eq!(orphans, vec![&Vertex::number(114, 0, 5), &Vertex::number(58, 5, 7)]);
+        assert_eq!(schematic.orphans().count() + schematic.parts().len(), schematic.numbers().count());
+        assert!(Schematic::parse("1*2").orphans().next().is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let options = SchematicOptions { wrap: true, ..Default::default() };
+        let schematic = Schematic::parse_with(include_str!("../input/sample.txt"), &options);
+        let json = serde_json::to_string(&schematic).unwrap();
+        let loaded: Schematic = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.vertices, schematic.vertices);
+        assert_eq!(loaded.adjacency, schematic.adjacency);
+        assert_eq!(loaded.grid, schematic.grid);
+        assert_eq!(loaded.options(), &options);
+        assert_eq!(loaded.at(1, 0), Some(&Vertex::number(467, 0, 0)));
+
+        let parts = schematic.parts();
+        let json = serde_json::to_string(&parts).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<PartNumber>>(&json).unwrap(), parts);
+        let gears = schematic.gears(GEAR_SYMBOL, GEAR_ARITY);
+        let json = serde_json::to_string(&gears).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Gear>>(&json).unwrap(), gears);
+
+        let bad = r#"{"vertices":[],"adjacency":[[0]],"lines":[],"options":{"storage":"Sparse"}}"#;
+        let err = serde_json::from_str::<Schematic>(bad).unwrap_err();
+        assert!(err.to_string().contains("adjacency"));
+
+        let options: SchematicOptions = serde_json::from_str(r#"{"alphabet":{"blanks":["."],"radix":16,"symbols":null}}"#).unwrap();
+        assert_eq!(options.alphabet.radix(), 16);
+        let err = serde_json::from_str::<SchematicOptions>(r#"{"alphabet":{"blanks":[],"radix":40,"symbols":null}}"#).unwrap_err();
+        assert!(err.to_string().contains("radix 40"));
+    }
+
+    #[test]
+    fn test_input_sources() {
+        let sample = include_str!("../input/sample.txt");
+        let expected = Schematic::parse(sample).part_numbers();
+        assert_eq!(part_numbers(sample.lines()), expected);
+        assert_eq!(Schematic::from_reader(sample.as_bytes()).unwrap().part_numbers(), expected);
+        assert_eq!(Schematic::from_path("input/sample.txt").unwrap().part_numbers(), expected);
+        assert!(Schematic::from_path("input/missing.txt").is_err());
+
+        let wrap = SchematicOptions { wrap: true, ..Default::default() };
+        let torus = Schematic::from_reader_with("..3\n...\n*..".as_bytes(), &wrap).unwrap();
+        assert_eq!(torus.part_numbers(), vec![3]);
+        assert_eq!(gear_ratios(["1*1"]), vec![1]);
+
+        let grid: Vec<Vec<char>> = sample.lines().map(|line| line.chars().collect()).collect();
+        assert_eq!(Schematic::from_grid(&grid).part_numbers(), expected);
+        let grid = vec![vec!['.', '.', '3'], vec!['.', '.', '.'], vec!['*', '.', '.']];
+        assert_eq!(Schematic::from_grid_with(&grid, &wrap).part_numbers(), vec![3]);
+        assert!(Schematic::from_grid(&[]).vertices().next().is_none());
+    }
+
+    #[test]
+    fn test_unicode_columns() {
+        // Two-byte symbols mustn't push the 1 out of reach of the *.
+        let schematic = Schematic::parse("éé.1\n...*");
+        assert_eq!(schematic.part_numbers(), vec![1]);
+        assert_eq!(schematic.at(3, 0), Some(&Vertex::number(1, 0, 3)));
+        assert_eq!(schematic.symbols().next(), Some(&Vertex::symbol('é', 0, 0)));
+        assert_eq!(Schematic::parse("€12\n...").parts()[0].adjacent_symbols[0].position, Point::new(0, 0));
+
+        let mut edited = schematic.clone();
+        edited.set_cell(1, 0, '§');
+        assert_eq!(edited.text(edited.at(1, 0).unwrap()), Some("§"));
+        assert_eq!(edited.part_numbers(), vec![1]);
+
+        assert!(Schematic::try_parse("é.\n.1").is_ok());
+        assert_eq!(
+            Schematic::try_parse("é.1\n.*\t").unwrap_err(),
+            SchematicError::ControlCharacter { line: 2, column: 2, found: '\t' },
+        );
+    }
+
+    #[cfg(feature = "graphemes")]
+    #[test]
+    fn test_grapheme_columns() {
+        // An e and a combining acute accent make one cell.
+        let schematic = Schematic::parse("e\u{301}.1\n..*");
+        assert_eq!(schematic.at(2, 0), Some(&Vertex::number(1, 0, 2)));
+        assert_eq!(schematic.part_numbers(), vec![1]);
+        assert_eq!(schematic.text(schematic.symbols().next().unwrap()), Some("e\u{301}"));
+    }
+
+    #[test]
+    fn test_ragged_line_policies() {
+        let input = "1*\n...5\n..#";
+        let options = |ragged| SchematicOptions { ragged, ..Default::default() };
+
+        let kept = Schematic::parse(input);
+        assert_eq!(kept.ragged(), Some(RaggedLines::Keep));
+        assert_eq!(kept.lines, vec!["1*", "...5", "..#"]);
+        assert_eq!(Schematic::parse("1*\n..").ragged(), None);
+
+        let padded = Schematic::parse_with(input, &options(RaggedLines::Pad));
+        assert_eq!(padded.ragged(), Some(RaggedLines::Pad));
+        assert_eq!(padded.lines, vec!["1*..", "...5", "..#."]);
+        assert_eq!(padded.part_numbers(), vec![1, 5]);
+
+        let truncated = Schematic::parse_with(input, &options(RaggedLines::Truncate));
+        assert_eq!(truncated.ragged(), Some(RaggedLines::Truncate));
+        assert_eq!(truncated.lines, vec!["1*", "..", ".."]);
+        assert_eq!(truncated.part_numbers(), vec![1]);
+
+        assert_eq!(
+            Schematic::try_parse_with(input, &options(RaggedLines::Reject)).unwrap_err(),
+            SchematicError::Ragged { line: 2, expected: 2, found: 4 },
+        );
+        assert!(Schematic::try_parse_with(input, &options(RaggedLines::Pad)).is_ok());
+        assert_eq!(Schematic::parse_with(input, &options(RaggedLines::Reject)).ragged(), Some(RaggedLines::Reject));
+    }
+
+    #[test]
+    fn test_alphabet() {
+        let input = "12+.\n..#.\n..ff";
+        assert_eq!(Schematic::parse(input).part_numbers(), vec![12]);
+
+        let mut plus_blank = SchematicOptions::default();
+        plus_blank.alphabet.blanks = vec!['.', '+'];
+        let schematic = Schematic::parse_with(input, &plus_blank);
+        assert_eq!(schematic.symbols().count(), 3);
+        assert_eq!(schematic.at(2, 0), None);
+
+        let hex = SchematicOptions { alphabet: Alphabet::default().with_radix(16).unwrap(), ..Default::default() };
+        assert_eq!(Schematic::parse_with(input, &hex).part_numbers(), vec![0x12, 0xff]);
+        assert_eq!(Alphabet::default().with_radix(40), Err(InvalidRadix(40)));
+        assert_eq!(Alphabet::default().with_radix(1), Err(InvalidRadix(1)));
+
+        let mut only_hash = SchematicOptions::default();
+        only_hash.alphabet.symbols = Some(vec!['#']);
+        let schematic = Schematic::parse_with(input, &only_hash);
+        assert_eq!(schematic.symbols().count(), 1);
+        assert_eq!(schematic.part_numbers(), vec![12]);
+        assert_eq!(schematic.at(3, 0), None);
+    }
+
+    #[test]
+    fn test_tokens() {
+        let tokens = vec!["::".to_string(), "=>".to_string(), "=".to_string()];
+        let options = SchematicOptions { tokens, ..Default::default() };
+        assert_eq!(analyze_line("1::2=>3.-=", 0, &options), vec![
+            Vertex::number(1, 0, 0),
+            Vertex::token(':', 0, 1, 2),
+            Vertex::number(2, 0, 3),
+            Vertex::token('=', 0, 4, 5),
+            Vertex::number(3, 0, 6),
+            Vertex::symbol('-', 0, 8),
+            Vertex::symbol('=', 0, 9),
+        ]);
+        assert_eq!(analyze_line(":::", 0, &options).len(), 2);
+
+        let schematic = Schematic::parse_with("12..\n..::\n=>..\n...5", &options);
+        assert_eq!(schematic.part_numbers(), vec![12]);
+        let symbols: Vec<&str> = schematic.symbols().filter_map(|v| schematic.text(v)).collect();
+        assert_eq!(symbols, vec!["::", "=>"]);
+        assert_eq!(schematic.at(3, 1), Some(&Vertex::token(':', 1, 2, 3)));
+        assert_eq!(Schematic::parse("12..\n..::").symbols().count(), 2);
+    }
+
+    #[test]
+    fn test_parts_touching() {
+        let schematic = Schematic::parse("12.12\n..*..\n12#.*");
+        let parts = schematic.parts_touching(2);
+        assert_eq!(parts.len(), 1);
+        assert_eq!((parts[0].y, parts[0].min_x), (2, 0));
+        assert_eq!(parts[0].symbol_chars(), BTreeSet::from(['*', '#']));
+        assert_eq!(schematic.parts_touching(1), schematic.parts());
+        assert!(schematic.parts_touching(3).is_empty());
+
+        let twice = Schematic::parse("*1*").parts();
+        assert_eq!(twice[0].adjacent_symbols.len(), 2);
+        assert_eq!(twice[0].symbol_chars(), BTreeSet::from(['*']));
+    }
+
+    #[test]
+    fn test_symbols_for() {
+        let schematic = Schematic::parse("12.12\n..*..\n12#..");
+        for part in schematic.parts() {
+            assert_eq!(schematic.symbols_for(&part), part.adjacent_symbols);
+        }
+        let stranger = PartNumber { value: 99, y: 0, min_x: 0, max_x: 1, adjacent_symbols: vec![] };
+        assert!(schematic.symbols_for(&stranger).is_empty());
+
+        // Numbers wider than their decimal value.
+        let hex = SchematicOptions { alphabet: Alphabet::default().with_radix(16).unwrap(), ..Default::default() };
+        for schematic in [Schematic::parse("007*"), Schematic::parse_with("ff*", &hex)] {
+            let parts = schematic.parts();
+            assert_eq!(parts.len(), 1);
+            assert_eq!(parts[0].adjacent_symbols.len(), 1);
+            assert_eq!(schematic.symbols_for(&parts[0]), parts[0].adjacent_symbols);
+        }
+    }
+
+    #[test]
+    fn test_configurable_gears() {
+        let schematic = Schematic::parse("2.3.4\n.*#*.\n5....");
+        let gears = schematic.gears('*', 2);
+        assert_eq!(gears.len(), 1);
+        assert_eq!(gears[0], Gear { symbol: '*', position: Point::new(3, 1), numbers: vec![3, 4], ratio: Some(12) });
+        assert_eq!(schematic.gears('*', 3)[0].numbers, vec![2, 3, 5]);
+        assert_eq!(schematic.gears('#', 1)[0].ratio, Some(3));
+        assert!(schematic.gears('+', 2).is_empty());
+        assert_eq!(schematic.gear_ratios(), vec![12]);
+    }
+
+    #[test]
+    fn test_symbol_filtered_queries() {
+        let schematic = Schematic::parse(include_str!("../input/sample.txt"));
+        assert_eq!(schematic.numbers_adjacent_to_symbol('#'), vec![633]);
+        assert_eq!(schematic.numbers_adjacent_to_symbol('*'), vec![467, 35, 617, 755, 598]);
+        assert_eq!(schematic.numbers_adjacent_to_any_of(&['#', '$', '+']), vec![633, 592, 664]);
+        assert!(schematic.numbers_adjacent_to_symbol('%').is_empty());
+        assert!(schematic.numbers_adjacent_to_any_of(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_leading_zeros_keep_their_width() {
+        let schematic = Schematic::parse("007*\n....\n0.1.");
+        assert_eq!(schematic.part_numbers(), vec![7]);
+        let seven = schematic.numbers().next().unwrap();
+        assert_eq!((seven.min_x, seven.max_x), (0, 2));
+    }
+
+    #[test]
+    fn test_ragged_lines() {
+        let schematic = Schematic::parse("1\n.*\n......2");
+        assert_eq!(schematic.part_numbers(), vec![1]);
+    }
+
+    #[test]
+    fn test_large_numbers() {
+        let schematic = Schematic::parse("4294967296*4294967296");
+        assert_eq!(schematic.part_numbers(), vec![4294967296, 4294967296]);
+        assert_eq!(schematic.part_number_sum(), Ok(8589934592));
+        assert_eq!(schematic.gears('*', 2)[0].ratio, None);
+        assert_eq!(schematic.gear_ratios(), vec![u64::MAX]);
+        assert_eq!(schematic.gear_ratio_sum(), Err(OverflowError));
+
+        let schematic = Schematic::parse("4000000000*4000000000");
+        assert_eq!(schematic.gear_ratio_sum(), Ok(16_000_000_000_000_000_000));
+
+        let schematic = Schematic::parse("18446744073709551615*1");
+        assert_eq!(schematic.part_number_sum(), Err(OverflowError));
+
+        let sample = Schematic::parse(include_str!("../input/sample.txt"));
+        assert_eq!(sample.part_number_sum(), Ok(4361));
+        assert_eq!(sample.gear_ratio_sum(), Ok(467835));
+    }
+
+    #[test]
+    fn test_try_parse() {
+        let sample = include_str!("../input/sample.txt");
+        assert_eq!(Schematic::try_parse(sample).unwrap().part_numbers(), Schematic::parse(sample).part_numbers());
+        assert_eq!(Schematic::try_parse("12.\r\n.*.\r\n").unwrap().part_numbers(), vec![12]);
+
+        assert_eq!(Schematic::try_parse("").unwrap_err(), SchematicError::Empty);
+        assert_eq!(Schematic::try_parse("\n...").unwrap_err(), SchematicError::Empty);
+        assert_eq!(
+            Schematic::try_parse("...\n..\n...").unwrap_err(),
+            SchematicError::Ragged { line: 2, expected: 3, found: 2 },
+        );
+        let tab = Schematic::try_parse("1.*\n.\t.").unwrap_err();
+        assert_eq!(tab, SchematicError::ControlCharacter { line: 2, column: 1, found: '\t' });
+        assert_eq!(tab.to_string(), "control character '\\t' at line 2, column 1");
+        assert_eq!(
+            Schematic::try_parse(".99999999999999999999*").unwrap_err(),
+            SchematicError::NumberTooLarge { line: 1, column: 1, digits: "99999999999999999999".to_string() },
+        );
+
+        // Parsing leniently keeps the run whole, as neither a number nor a
+        // symbol.
+        let huge = Schematic::parse("123456789012345678901234\n5...");
+        assert!(huge.part_numbers().is_empty());
+        assert_eq!(huge.symbols().count(), 0);
+        let overflow = huge.vertices().find(|vertex| vertex.data == Data::Overflow).unwrap();
+        assert_eq!((overflow.min_x, overflow.max_x), (0, 23));
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let mut dot = vec![];
+        Schematic::parse("12*\n..\"").to_dot(&mut dot).unwrap();
+        assert_eq!(String::from_utf8(dot).unwrap(), "\
+graph schematic {
+  v0_0 [label=\"12\", shape=box];
+  v0_2 [label=\"*\", shape=circle];
+  v1_2 [label=\"\\\"\", shape=circle];
+  v0_0 -- v0_2;
+  v0_0 -- v1_2;
+  v0_2 -- v1_2;
+}
+");
+    }
+
+    #[cfg(feature = "petgraph")]
+    #[test]
+    fn test_to_graph() {
+        let schematic = Schematic::parse(include_str!("../input/sample.txt"));
+        let graph = schematic.to_graph();
+        assert_eq!(graph.node_count(), 16);
+        assert_eq!(graph.edge_count(), 8);
+        let gear = graph.node_indices().find(|node| graph[*node] == Vertex::symbol('*', 1, 3)).unwrap();
+        let mut numbers: Vec<u64> = graph.neighbors(gear).filter_map(|node| graph[node].as_number()).collect();
+        numbers.sort();
+        assert_eq!(numbers, vec![35, 467]);
+    }
+
+    #[test]
+    fn test_adjacency_options() {
+        let input = "1.2.\n.*..\n..3#";
+        let parse = |adjacency| Schematic::parse_with(input, &SchematicOptions { adjacency, ..Default::default() });
+        assert_eq!(parse(Adjacency::Eight).part_numbers(), vec![1, 2, 3]);
+        assert_eq!(parse(Adjacency::Eight).part_numbers(), Schematic::parse(input).part_numbers());
+        assert_eq!(parse(Adjacency::Four).part_numbers(), vec![3]);
+        assert_eq!(parse(Adjacency::Radius(1)).part_numbers(), vec![1, 2, 3]);
+        assert!(parse(Adjacency::Radius(0)).part_numbers().is_empty());
+
+        let far = "5....\n.....\n..*..";
+        assert!(Schematic::parse(far).part_numbers().is_empty());
+        let far = Schematic::parse_with(far, &SchematicOptions { adjacency: Adjacency::Radius(2), ..Default::default() });
+        assert_eq!(far.part_numbers(), vec![5]);
+        assert_eq!(far.gears('*', 1)[0].numbers, vec![5]);
+        assert_eq!(far.options().adjacency, Adjacency::Radius(2));
+    }
+
+    #[test]
+    fn test_point_queries() {
+        let schematic = Schematic::parse(include_str!("../input/sample.txt"));
+        assert_eq!(schematic.at(1, 0), Some(&Vertex::number(467, 0, 0)));
+        assert_eq!(schematic.at(3, 1), Some(&Vertex::symbol('*', 1, 3)));
+        assert_eq!(schematic.at(4, 1), None);
+        assert_eq!(schematic.at(40, 1), None);
+
+        assert_eq!(schematic.adjacent_to_point(3, 1), schematic.adjacent(&Vertex::symbol('*', 1, 3)).collect::<Vec<_>>());
+        assert_eq!(schematic.adjacent_to_point(4, 0), vec![&Vertex::number(114, 0, 5), &Vertex::symbol('*', 1, 3)]);
+        // 467 covers the cell, so only what's around it counts.
+        assert_eq!(schematic.adjacent_to_point(2, 0), vec![&Vertex::symbol('*', 1, 3)]);
+        assert!(schematic.adjacent_to_point(9, 9).is_empty());
+    }
+
+    #[test]
+    fn test_set_cell() {
+        let sample = include_str!("../input/sample.txt");
+        let mut schematic = Schematic::parse(sample);
+        // Join 114 to a new gear beside 467.
+        schematic.set_cell(4, 1, '*');
+        schematic.set_cell(5, 0, '4');
+        let mut lines: Vec<String> = sample.lines().map(str::to_string).collect();
+        lines[1].replace_range(4..5, "*");
+        lines[0].replace_range(5..6, "4");
+        let expected = Schematic::from_lines(&lines);
+        assert_eq!(schematic.vertices, expected.vertices);
+        assert_eq!(schematic.adjacency, expected.adjacency);
+        assert_eq!(schematic.grid, expected.grid);
+        assert_eq!(schematic.part_numbers(), expected.part_numbers());
+        assert_eq!(schematic.gear_ratios(), expected.gear_ratios());
+
+        // Splitting a number shifts every later vertex along.
+        schematic.set_cell(3, 2, '+');
+        lines[2].replace_range(3..4, "+");
+        let expected = Schematic::from_lines(&lines);
+        assert_eq!(schematic.adjacency, expected.adjacency);
+        assert_eq!(schematic.grid, expected.grid);
+        assert_eq!(schematic.part_numbers(), expected.part_numbers());
+
+        let wrap = SchematicOptions { wrap: true, ..Default::default() };
+        let mut torus = Schematic::parse_with("...\n...\n..3", &wrap);
+        torus.set_cell(0, 0, '#');
+        assert_eq!(torus.part_numbers(), vec![3]);
+        torus.set_cell(0, 0, '.');
+        assert!(torus.part_numbers().is_empty());
+    }
+
+    #[test]
+    fn test_sparse_storage() {
+        let sparse = SchematicOptions { storage: Storage::Sparse, ..Default::default() };
+        for input in [include_str!("../input/sample.txt"), include_str!("../input/input.txt")] {
+            let dense = Schematic::parse(input);
+            let schematic = Schematic::parse_with(input, &sparse);
+            assert_eq!(schematic.adjacency, dense.adjacency);
+            assert_eq!(schematic.parts(), dense.parts());
+            assert_eq!(schematic.gear_ratios(), dense.gear_ratios());
+        }
+
+        let mut schematic = Schematic::parse_with(include_str!("../input/sample.txt"), &sparse);
+        assert_eq!(schematic.at(2, 0), Some(&Vertex::number(467, 0, 0)));
+        assert_eq!(schematic.at(3, 0), None);
+        assert_eq!(schematic.adjacent_to_point(4, 0).len(), 2);
+        schematic.set_cell(3, 2, '+');
+        let mut lines: Vec<String> = include_str!("../input/sample.txt").lines().map(str::to_string).collect();
+        lines[2].replace_range(3..4, "+");
+        let expected = Schematic::from_lines(&lines);
+        assert_eq!(schematic.adjacency, expected.adjacency);
+        assert_eq!(schematic.at(4, 2), expected.at(4, 2));
+        assert_eq!(schematic.part_numbers(), expected.part_numbers());
+    }
+
+    #[test]
+    fn test_components() {
+        let schematic = Schematic::parse("1.2\n*..\n..3\n.#4");
+        let components = schematic.components();
+        let sums: Vec<Option<u64>> = components.iter().map(|component| component.sum).collect();
+        assert_eq!(sums, vec![Some(1), Some(2), Some(7)]);
+        assert_eq!(components[0].vertices, vec![Vertex::number(1, 0, 0), Vertex::symbol('*', 1, 0)]);
+        assert_eq!(components[2].vertices.len(), 3);
+
+        let sample = Schematic::parse(include_str!("../input/sample.txt")).components();
+        let largest = sample.iter().max_by_key(|component| component.sum).unwrap();
+        assert_eq!(largest.sum, Some(755 + 598));
+        assert_eq!(sample.iter().map(|component| component.vertices.len()).sum::<usize>(), 16);
+        assert_eq!(Schematic::parse("9*18446744073709551615").components()[0].sum, None);
+    }
+
+    #[test]
+    fn test_symbol_stats() {
+        let stats = Schematic::parse(include_str!("../input/sample.txt")).symbol_stats();
+        assert_eq!(stats.keys().copied().collect::<String>(), "#$*+");
+        let gears = &stats[&'*'];
+        assert_eq!((gears.count, gears.touching_numbers), (3, 3));
+        assert_eq!(gears.by_adjacent_numbers, BTreeMap::from([(1, 1), (2, 2)]));
+
+        let stats = Schematic::parse("#.#\n..1").symbol_stats();
+        assert_eq!(stats[&'#'], SymbolStats {
+            count: 2,
+            touching_numbers: 1,
+            by_adjacent_numbers: BTreeMap::from([(0, 1), (1, 1)]),
+        });
+        assert!(Schematic::parse("").symbol_stats().is_empty());
+    }
+
+    #[test]
+    fn test_wrap() {
+        let input = "..3\n...\n*..";
+        let wrap = SchematicOptions { wrap: true, ..Default::default() };
+        assert!(Schematic::parse(input).part_numbers().is_empty());
+        assert_eq!(Schematic::parse_with(input, &wrap).part_numbers(), vec![3]);
+
+        let four = SchematicOptions { adjacency: Adjacency::Four, wrap: true, ..Default::default() };
+        assert!(Schematic::parse_with(input, &four).part_numbers().is_empty());
+        let edges = "7...#\n.....";
+        assert_eq!(Schematic::parse_with(edges, &four).part_numbers(), vec![7]);
+
+        // A number as wide as the grid wraps onto itself, not a neighbour.
+        let narrow = Schematic::parse_with("12\n..", &wrap);
+        assert!(narrow.adjacent(&Vertex::number(12, 0, 0)).next().is_none());
+
+        let sample = include_str!("../input/sample.txt");
+        assert_eq!(Schematic::parse_with(sample, &wrap).part_numbers(), Schematic::parse(sample).part_numbers());
+        // An empty grid has nothing to wrap onto.
+        let empty = Schematic::parse_with("", &wrap);
+        assert!(empty.adjacent_to_point(0, 0).is_empty());
+        assert!(Schematic::parse_with("\n\n", &wrap).adjacent_to_point(0, 1).is_empty());
+    }
+
     #[test]
     fn test_gear_ratio_with_sample() {
         let sample_input = read_input("input/sample.txt");
-        let sum: u32 = gear_ratios(sample_input).iter().sum();
+        let sum: u64 = gear_ratios(sample_input).iter().sum();
         assert_eq!(sum, 467835);
     }
 
     #[test]
     fn test_gear_ratios_with_input() {
         let input = read_input("input/input.txt");
-        let sum: u32 = gear_ratios(input).iter().sum();
+        let sum: u64 = gear_ratios(input).iter().sum();
         assert_eq!(sum, 91031374);
     }
 }
@@ -0,0 +1,151 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::Vertex;
+
+/// Which cells around a number or symbol count as touching it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Adjacency {
+    /// The eight cells around each covered cell, diagonals included, as in
+    /// the puzzle.
+    #[default]
+    Eight,
+    /// Only the cells directly above, below, left or right of a covered cell.
+    Four,
+    /// Every cell within this many rows and columns of a covered cell.
+    Radius(usize),
+}
+
+impl Adjacency {
+    /// How many rows or columns away a touching cell can be.
+    pub fn radius(self) -> usize {
+        match self {
+            Adjacency::Eight | Adjacency::Four => 1,
+            Adjacency::Radius(r) => r,
+        }
+    }
+
+    /// Whether the cell at `x`, `y`, which is within `radius` of `vertex`,
+    /// touches it. The cell may be off the edge of the grid.
+    pub(crate) fn reaches(self, vertex: &Vertex, x: isize, y: isize) -> bool {
+        match self {
+            Adjacency::Eight | Adjacency::Radius(_) => true,
+            Adjacency::Four => y == vertex.y as isize || (vertex.min_x as isize..=vertex.max_x as isize).contains(&x),
+        }
+    }
+}
+
+/// How a schematic finds what covers a cell.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Storage {
+    /// A slot for every cell, so lookups are direct.
+    #[default]
+    Dense,
+    /// Only each line's numbers and symbols, searched when looking a cell up.
+    /// Uses far less memory when most cells are `.`.
+    Sparse,
+}
+
+/// Which characters are blanks, digits and symbols.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Alphabet {
+    /// Characters that are neither digits nor symbols, even if they'd
+    /// otherwise be one.
+    pub blanks: Vec<char>,
+    /// The base numbers are written in, from 2 to 36. Past 10 the digits
+    /// include letters, in either case.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_radix"))]
+    radix: u32,
+    /// The only characters that are symbols, or `None` for any character
+    /// that isn't a blank or a digit. Characters left out are blanks.
+    pub symbols: Option<Vec<char>>,
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Self { blanks: vec!['.'], radix: 10, symbols: None }
+    }
+}
+
+impl Alphabet {
+    /// The same alphabet with numbers written in base `radix`, which must be
+    /// from 2 to 36.
+    pub fn with_radix(self, radix: u32) -> Result<Self, InvalidRadix> {
+        if !(2..=36).contains(&radix) {
+            return Err(InvalidRadix(radix));
+        }
+        Ok(Self { radix, ..self })
+    }
+
+    pub fn radix(&self) -> u32 {
+        self.radix
+    }
+
+    pub(crate) fn is_blank(&self, c: char) -> bool {
+        self.blanks.contains(&c)
+    }
+
+    pub(crate) fn is_digit(&self, c: char) -> bool {
+        c.is_digit(self.radix)
+    }
+
+    pub(crate) fn is_symbol(&self, c: char) -> bool {
+        self.symbols.as_ref().is_none_or(|symbols| symbols.contains(&c))
+    }
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_radix<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let radix = <u32 as serde::Deserialize>::deserialize(deserializer)?;
+    Alphabet::default().with_radix(radix).map(|alphabet| alphabet.radix).map_err(serde::de::Error::custom)
+}
+
+/// A radix outside 2 to 36, from `Alphabet::with_radix`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InvalidRadix(pub u32);
+
+impl fmt::Display for InvalidRadix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "radix {} is not from 2 to 36", self.0)
+    }
+}
+
+impl Error for InvalidRadix {}
+
+/// What to do with lines that aren't as wide as the others.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RaggedLines {
+    /// Read each line as it is.
+    #[default]
+    Keep,
+    /// Blank out each line to the width of the widest.
+    Pad,
+    /// Cut each line to the width of the narrowest.
+    Truncate,
+    /// Fail with `SchematicError::Ragged`. Only `Schematic::try_parse_with`
+    /// can fail; everything else keeps the lines as they are.
+    Reject,
+}
+
+/// How `Schematic::parse_with` reads a schematic.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct SchematicOptions {
+    pub adjacency: Adjacency,
+    /// Treat the grid as a torus: the left and right edges touch, as do the
+    /// top and bottom. Ragged rows are treated as padded to the widest.
+    pub wrap: bool,
+    /// Runs of characters, such as `::` or `=>`, to read as one symbol. The
+    /// longest token starting at a cell wins over anything else there. The
+    /// symbol is named after the token's first character; `Schematic::text`
+    /// gives the whole token.
+    pub tokens: Vec<String>,
+    pub storage: Storage,
+    pub alphabet: Alphabet,
+    pub ragged: RaggedLines,
+}
@@ -0,0 +1,110 @@
+use crate::{Point, Schematic};
+
+/// How `Schematic::symbols_with_exactly` combines the numbers next to a
+/// symbol. Implemented by `Reduction` and by any `Fn(&[u64]) -> T`.
+pub trait Reduce {
+    type Output;
+
+    fn reduce(&self, numbers: &[u64]) -> Self::Output;
+}
+
+/// The usual ways to combine numbers. Each gives `None` on overflow, and
+/// `Min` and `Max` give `None` for no numbers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Reduction {
+    Product,
+    Sum,
+    Min,
+    Max,
+}
+
+impl Reduce for Reduction {
+    type Output = Option<u64>;
+
+    fn reduce(&self, numbers: &[u64]) -> Option<u64> {
+        let mut numbers = numbers.iter().copied();
+        match self {
+            Reduction::Product => numbers.try_fold(1u64, u64::checked_mul),
+            Reduction::Sum => numbers.try_fold(0u64, u64::checked_add),
+            Reduction::Min => numbers.min(),
+            Reduction::Max => numbers.max(),
+        }
+    }
+}
+
+impl<F, T> Reduce for F
+where
+    F: Fn(&[u64]) -> T,
+{
+    type Output = T;
+
+    fn reduce(&self, numbers: &[u64]) -> T {
+        self(numbers)
+    }
+}
+
+/// A symbol next to a given number of numbers, and what they reduce to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SymbolReduction<T> {
+    pub symbol: char,
+    pub position: Point,
+    /// The adjacent numbers, in reading order.
+    pub numbers: Vec<u64>,
+    pub value: T,
+}
+
+impl Schematic {
+    /// Every `symbol` next to exactly `k` numbers, in reading order, with
+    /// those numbers combined by `reduction`. The puzzle's gear ratios are
+    /// `symbols_with_exactly('*', 2, Reduction::Product)`.
+    pub fn symbols_with_exactly<R: Reduce>(
+        &self,
+        symbol: char,
+        k: usize,
+        reduction: R,
+    ) -> Vec<SymbolReduction<R::Output>> {
+        self.symbols()
+            .filter(|vertex| vertex.as_symbol() == Some(symbol))
+            .filter_map(|vertex| {
+                let numbers: Vec<u64> = self.adjacent(vertex).filter_map(|v| v.as_number()).collect();
+                (numbers.len() == k).then(|| SymbolReduction {
+                    symbol,
+                    position: Point::new(vertex.min_x, vertex.y),
+                    value: reduction.reduce(&numbers),
+                    numbers,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbols_with_exactly() {
+        let schematic = Schematic::parse(include_str!("../input/sample.txt"));
+        let ratios: Vec<Option<u64>> = schematic
+            .symbols_with_exactly('*', 2, Reduction::Product)
+            .into_iter()
+            .map(|reduced| reduced.value)
+            .collect();
+        assert_eq!(ratios, schematic.gears('*', 2).into_iter().map(|gear| gear.ratio).collect::<Vec<_>>());
+
+        let sums = schematic.symbols_with_exactly('*', 2, Reduction::Sum);
+        assert_eq!(sums[0].numbers, vec![467, 35]);
+        assert_eq!(sums[0].position, Point::new(3, 1));
+        assert_eq!(sums[0].value, Some(502));
+        assert_eq!(schematic.symbols_with_exactly('*', 2, Reduction::Min)[1].value, Some(598));
+        assert_eq!(schematic.symbols_with_exactly('*', 2, Reduction::Max)[1].value, Some(755));
+        assert_eq!(schematic.symbols_with_exactly('*', 1, Reduction::Min)[0].value, Some(617));
+        assert_eq!(Reduction::Max.reduce(&[]), None);
+
+        let spread = schematic.symbols_with_exactly('*', 2, |numbers: &[u64]| numbers[0].abs_diff(numbers[1]));
+        assert_eq!(spread.iter().map(|reduced| reduced.value).collect::<Vec<_>>(), vec![432, 157]);
+        assert_eq!(schematic.symbols_with_exactly('#', 1, Reduction::Sum)[0].value, Some(633));
+        assert_eq!(Schematic::parse("99999999999*99999999999").symbols_with_exactly('*', 2, Reduction::Product)[0].value, None);
+    }
+}
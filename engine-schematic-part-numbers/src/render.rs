@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+use crate::{cell_range, Point, Schematic, Vertex, GEAR_ARITY, GEAR_SYMBOL};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RenderFormat {
+    /// Colored with ANSI escape codes, for a terminal.
+    Ansi,
+    /// A `<pre>` block with each number and symbol in a classed `<span>`.
+    Html,
+}
+
+/// What a number or symbol counts as, which decides its color.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Kind {
+    Part,
+    Orphan,
+    Gear,
+    Symbol,
+}
+
+impl Kind {
+    fn ansi(self) -> &'static str {
+        match self {
+            Kind::Part => "\x1b[32m",
+            Kind::Orphan => "\x1b[31m",
+            Kind::Gear => "\x1b[1;33m",
+            Kind::Symbol => "\x1b[36m",
+        }
+    }
+
+    fn class(self) -> &'static str {
+        match self {
+            Kind::Part => "part",
+            Kind::Orphan => "orphan",
+            Kind::Gear => "gear",
+            Kind::Symbol => "symbol",
+        }
+    }
+}
+
+impl Schematic {
+    /// Writes the schematic with part numbers in green, numbers touching no
+    /// symbol in red, the puzzle's gears in yellow and other symbols in cyan.
+    /// In HTML those are the `part`, `orphan`, `gear` and `symbol` classes.
+    pub fn render<W: Write>(&self, format: RenderFormat, mut writer: W) -> io::Result<()> {
+        let gears: HashSet<Point> = self
+            .gears(GEAR_SYMBOL, GEAR_ARITY)
+            .into_iter()
+            .map(|gear| gear.position)
+            .collect();
+        if format == RenderFormat::Html {
+            writeln!(writer, "<pre class=\"schematic\">")?;
+        }
+        for (y, line) in self.lines.iter().enumerate() {
+            let mut x = 0;
+            while let Some(cell) = cell_range(line, x) {
+                let Some(vertex) = self.at(x, y) else {
+                    write_text(&mut writer, format, &line[cell])?;
+                    x += 1;
+                    continue;
+                };
+                let kind = self.kind(vertex, &gears);
+                let text = self.text(vertex).unwrap_or_default();
+                match format {
+                    RenderFormat::Ansi => write!(writer, "{}{text}\x1b[0m", kind.ansi())?,
+                    RenderFormat::Html => {
+                        write!(writer, "<span class=\"{}\">", kind.class())?;
+                        write_text(&mut writer, format, text)?;
+                        write!(writer, "</span>")?;
+                    },
+                }
+                x = vertex.max_x + 1;
+            }
+            writeln!(writer)?;
+        }
+        if format == RenderFormat::Html {
+            writeln!(writer, "</pre>")?;
+        }
+        Ok(())
+    }
+
+    fn kind(&self, vertex: &Vertex, gears: &HashSet<Point>) -> Kind {
+        if vertex.as_symbol().is_none() {
+            if vertex.as_number().is_none() || self.is_orphan(vertex) {
+                Kind::Orphan
+            } else {
+                Kind::Part
+            }
+        } else if gears.contains(&Point::new(vertex.min_x, vertex.y)) {
+            Kind::Gear
+        } else {
+            Kind::Symbol
+        }
+    }
+}
+
+/// Writes `text` as is, or escaped for HTML.
+fn write_text<W: Write>(writer: &mut W, format: RenderFormat, text: &str) -> io::Result<()> {
+    if format == RenderFormat::Ansi {
+        return write!(writer, "{text}");
+    }
+    for c in text.chars() {
+        match c {
+            '&' => write!(writer, "&amp;")?,
+            '<' => write!(writer, "&lt;")?,
+            '>' => write!(writer, "&gt;")?,
+            _ => write!(writer, "{c}")?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(schematic: &Schematic, format: RenderFormat) -> String {
+        let mut out = vec![];
+        schematic.render(format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_ansi() {
+        let sample = Schematic::parse(include_str!("../input/sample.txt"));
+        let rendered = render(&sample, RenderFormat::Ansi);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[0], "\x1b[32m467\x1b[0m..\x1b[31m114\x1b[0m..");
+        assert_eq!(lines[1], "...\x1b[1;33m*\x1b[0m......");
+        assert_eq!(lines[4], "\x1b[32m617\x1b[0m\x1b[36m*\x1b[0m......");
+    }
+
+    #[test]
+    fn test_html() {
+        let schematic = Schematic::parse("1<.\n..&\n*.7");
+        assert_eq!(
+            render(&schematic, RenderFormat::Html),
+            "<pre class=\"schematic\">\n\
+             <span class=\"part\">1</span><span class=\"symbol\">&lt;</span>.\n\
+             ..<span class=\"symbol\">&amp;</span>\n\
+             <span class=\"symbol\">*</span>.<span class=\"part\">7</span>\n\
+             </pre>\n",
+        );
+        assert_eq!(render(&Schematic::default(), RenderFormat::Html), "<pre class=\"schematic\">\n</pre>\n");
+    }
+}
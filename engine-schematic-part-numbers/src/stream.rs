@@ -0,0 +1,182 @@
+//! Reads a schematic a line at a time, keeping only three lines in memory.
+//! A line's part numbers and gears can only touch the lines either side of
+//! it, so they're final as soon as the line after it has been read. Only the
+//! puzzle's eight-cell adjacency is supported, and edges never wrap.
+
+use std::collections::VecDeque;
+
+use crate::{
+    analyze_line, cell_offsets, neighbours, Adjacency, Gear, PartNumber, SchematicOptions, Vertex, GEAR_ARITY, GEAR_SYMBOL,
+};
+
+/// A part number or gear that no later line can change.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Finalized {
+    Part(PartNumber),
+    Gear(Gear),
+}
+
+struct Row {
+    y: usize,
+    vertices: Vec<Vertex>,
+    /// Which of `vertices`, by index, occupies each cell.
+    cells: Vec<Option<usize>>,
+}
+
+/// Yields part numbers and gears in reading order as lines are read from
+/// `lines`.
+pub struct Stream<I> {
+    lines: I,
+    /// The line being finalized and the lines either side of it.
+    window: VecDeque<Row>,
+    lines_read: usize,
+    next_to_finalize: usize,
+    exhausted: bool,
+    finalized: VecDeque<Finalized>,
+    gear_symbol: char,
+    gear_arity: usize,
+    options: SchematicOptions,
+}
+
+impl<I, S> Stream<I>
+where
+    I: Iterator<Item = S>,
+    S: AsRef<str>,
+{
+    pub fn new<L>(lines: L) -> Self
+    where L: IntoIterator<IntoIter = I> {
+        Self {
+            lines: lines.into_iter(),
+            window: VecDeque::with_capacity(4),
+            lines_read: 0,
+            next_to_finalize: 0,
+            exhausted: false,
+            finalized: VecDeque::new(),
+            gear_symbol: GEAR_SYMBOL,
+            gear_arity: GEAR_ARITY,
+            options: SchematicOptions::default(),
+        }
+    }
+
+    /// Emit `symbol`s next to exactly `arity` numbers as gears, rather than
+    /// the puzzle's gears.
+    pub fn with_gear(self, symbol: char, arity: usize) -> Self {
+        Self { gear_symbol: symbol, gear_arity: arity, ..self }
+    }
+
+    fn read(&mut self, line: &str) {
+        let y = self.lines_read;
+        let vertices = analyze_line(line, y, &self.options);
+        let mut cells = vec![None; cell_offsets(line).len()];
+        for (index, vertex) in vertices.iter().enumerate() {
+            cells[vertex.span()].fill(Some(index));
+        }
+        self.window.push_back(Row { y, vertices, cells });
+        if self.window.len() > 3 {
+            self.window.pop_front();
+        }
+        self.lines_read += 1;
+    }
+
+    fn finalize_next(&mut self) {
+        let y = self.next_to_finalize;
+        self.next_to_finalize += 1;
+        let cell = |x: usize, y: usize| {
+            let row = self.window.iter().find(|row| row.y == y)?;
+            row.cells.get(x).copied().flatten().map(|index| &row.vertices[index])
+        };
+        let Some(row) = self.window.iter().find(|row| row.y == y) else { return };
+        for vertex in &row.vertices {
+            let around = neighbours(vertex, Adjacency::Eight, None, cell);
+            if let Some(part) = PartNumber::new(vertex, around.iter().copied()) {
+                self.finalized.push_back(Finalized::Part(part));
+            } else if let Some(gear) = Gear::new(vertex, around.into_iter(), self.gear_symbol, self.gear_arity) {
+                self.finalized.push_back(Finalized::Gear(gear));
+            }
+        }
+    }
+}
+
+impl<I, S> Iterator for Stream<I>
+where
+    I: Iterator<Item = S>,
+    S: AsRef<str>,
+{
+    type Item = Finalized;
+
+    fn next(&mut self) -> Option<Finalized> {
+        loop {
+            if let Some(finalized) = self.finalized.pop_front() {
+                return Some(finalized);
+            }
+            if self.exhausted {
+                if self.next_to_finalize >= self.lines_read {
+                    return None;
+                }
+                self.finalize_next();
+                continue;
+            }
+            match self.lines.next() {
+                Some(line) => {
+                    self.read(line.as_ref());
+                    if self.lines_read > self.next_to_finalize + 1 {
+                        self.finalize_next();
+                    }
+                },
+                None => self.exhausted = true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::Schematic;
+
+    fn split(items: Vec<Finalized>) -> (Vec<PartNumber>, Vec<Gear>) {
+        let mut parts = vec![];
+        let mut gears = vec![];
+        for item in items {
+            match item {
+                Finalized::Part(part) => parts.push(part),
+                Finalized::Gear(gear) => gears.push(gear),
+            }
+        }
+        (parts, gears)
+    }
+
+    #[test]
+    fn test_matches_schematic() {
+        for input in [include_str!("../input/sample.txt"), include_str!("../input/input.txt")] {
+            let schematic = Schematic::parse(input);
+            let (parts, gears) = split(Stream::new(input.lines()).collect());
+            assert_eq!(parts, schematic.parts());
+            assert_eq!(gears, schematic.gears(GEAR_SYMBOL, GEAR_ARITY));
+        }
+        let input = include_str!("../input/sample.txt");
+        let (_, gears) = split(Stream::new(input.lines()).with_gear('*', 1).collect());
+        assert_eq!(gears, Schematic::parse(input).gears('*', 1));
+    }
+
+    #[test]
+    fn test_emits_before_reading_everything() {
+        let pulled = Cell::new(0);
+        let lines = include_str!("../input/sample.txt").lines().inspect(|_| pulled.set(pulled.get() + 1));
+        let mut stream = Stream::new(lines);
+        assert!(matches!(stream.next(), Some(Finalized::Part(PartNumber { value: 467, .. }))));
+        assert_eq!(pulled.get(), 2);
+        assert!(matches!(stream.next(), Some(Finalized::Gear(Gear { ratio: Some(16345), .. }))));
+        assert_eq!(pulled.get(), 3);
+    }
+
+    #[test]
+    fn test_short_inputs() {
+        assert_eq!(Stream::new(Vec::<String>::new()).count(), 0);
+        let (parts, _) = split(Stream::new(["1*"]).collect());
+        assert_eq!(parts.len(), 1);
+    }
+}
@@ -0,0 +1,75 @@
+//! Reflections and rotations of a whole schematic. Numbers are read along
+//! rows, so only the transformations that keep rows as rows are here: a
+//! quarter turn or a transpose would stand each number on end.
+
+use crate::{Schematic, Vertex};
+
+impl Schematic {
+    /// The schematic reflected left to right. Numbers move but still read
+    /// left to right, so `467.` becomes `.467`.
+    pub fn mirror(&self) -> Schematic {
+        let width = self.grid.width();
+        self.remap(|vertex| (vertex.y, width - 1 - vertex.max_x))
+    }
+
+    /// The schematic reflected top to bottom.
+    pub fn flip(&self) -> Schematic {
+        let height = self.grid.height();
+        self.remap(|vertex| (height - 1 - vertex.y, vertex.min_x))
+    }
+
+    /// The schematic turned half way round.
+    pub fn rotate_180(&self) -> Schematic {
+        let (width, height) = (self.grid.width(), self.grid.height());
+        self.remap(|vertex| (height - 1 - vertex.y, width - 1 - vertex.max_x))
+    }
+
+    /// Redraws every number and symbol starting at the line and column
+    /// `place` gives, on a blank grid as wide as the widest line, and reads
+    /// the result with the same options.
+    fn remap<F: Fn(&Vertex) -> (usize, usize)>(&self, place: F) -> Schematic {
+        let blank = self.options.alphabet.blanks.first().copied().unwrap_or('.').to_string();
+        let mut cells = vec![vec![blank; self.grid.width()]; self.grid.height()];
+        for vertex in self.vertices() {
+            let (y, min_x) = place(vertex);
+            let row = &mut cells[y];
+            row[min_x..=min_x + vertex.max_x - vertex.min_x].fill(String::new());
+            row[min_x] = self.text(vertex).unwrap_or_default().to_string();
+        }
+        let lines = cells.into_iter().map(|row| row.concat()).collect();
+        Schematic::from_owned_lines(lines, &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Adjacency, SchematicOptions};
+
+    #[test]
+    fn test_sums_are_invariant() {
+        for input in [include_str!("../input/sample.txt"), include_str!("../input/input.txt")] {
+            for adjacency in [Adjacency::Eight, Adjacency::Four, Adjacency::Radius(2)] {
+                let schematic = Schematic::parse_with(input, &SchematicOptions { adjacency, ..Default::default() });
+                let sums = (schematic.part_number_sum(), schematic.gear_ratio_sum());
+                for transformed in [schematic.mirror(), schematic.flip(), schematic.rotate_180()] {
+                    assert_eq!((transformed.part_number_sum(), transformed.gear_ratio_sum()), sums);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_transforms() {
+        let schematic = Schematic::parse("467.\n...*\n12..");
+        assert_eq!(schematic.mirror().lines, vec![".467", "*...", "..12"]);
+        assert_eq!(schematic.flip().lines, vec!["12..", "...*", "467."]);
+        assert_eq!(schematic.rotate_180().lines, vec!["..12", "*...", ".467"]);
+        assert_eq!(schematic.mirror().mirror().lines, schematic.lines);
+        assert_eq!(schematic.rotate_180().vertices, schematic.mirror().flip().vertices);
+
+        // Short lines are padded to the widest.
+        assert_eq!(Schematic::parse("1*\n5..").mirror().lines, vec![".*1", "..5"]);
+        assert!(Schematic::default().mirror().vertices().next().is_none());
+    }
+}
@@ -1,6 +1,10 @@
 use std::collections::HashSet;
+use std::ops::RangeInclusive;
 
-#[derive(Debug, Eq, Hash, PartialEq)]
+/// A cell of a grid; `x` counts characters from the start of the line, or
+/// grapheme clusters with the `graphemes` feature.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
     pub x: usize,
     pub y: usize,
@@ -10,15 +14,105 @@ impl Point {
     pub fn new(x: usize, y: usize) -> Self {
         Self { x, y }
     }
+
+    /// The up to eight cells around this one, in reading order. Cells past
+    /// the top or left edge are left out.
+    pub fn neighbors8(self) -> impl Iterator<Item = Point> {
+        let center = SignedPoint::from(self);
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+            .filter(|offset| *offset != (0, 0))
+            .filter_map(move |(dx, dy)| center.offset(dx, dy).to_point())
+    }
+
+    /// The up to four cells above, left of, right of and below this one.
+    pub fn neighbors4(self) -> impl Iterator<Item = Point> {
+        self.neighbors8().filter(move |point| point.x == self.x || point.y == self.y)
+    }
+
+}
+
+/// A cell that may be past the top or left edge of a grid, so offsets can be
+/// worked out before knowing whether they land on it.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignedPoint {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl SignedPoint {
+    pub fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+
+    pub fn offset(self, dx: isize, dy: isize) -> Self {
+        Self::new(self.x + dx, self.y + dy)
+    }
+
+    /// The cell, unless it's past the top or left edge.
+    pub fn to_point(self) -> Option<Point> {
+        Some(Point::new(self.x.try_into().ok()?, self.y.try_into().ok()?))
+    }
+
+    /// The cell on a `width` by `height` torus, where a cell off one edge is
+    /// found on the opposite edge. An empty torus has no cells.
+    pub fn wrap(self, width: usize, height: usize) -> Option<Point> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+        Some(Point::new(
+            self.x.rem_euclid(width as isize) as usize,
+            self.y.rem_euclid(height as isize) as usize,
+        ))
+    }
+
+    /// `wrap` with `wrap` set to a width and height, or `to_point` without.
+    pub(crate) fn wrapped(self, wrap: Option<(usize, usize)>) -> Option<Point> {
+        match wrap {
+            Some((width, height)) => self.wrap(width, height),
+            None => self.to_point(),
+        }
+    }
+}
+
+impl From<Point> for SignedPoint {
+    fn from(point: Point) -> Self {
+        Self::new(point.x as isize, point.y as isize)
+    }
+}
+
+/// The cells from `min` to `max`, both included.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Rect {
+    pub fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
 }
 
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Data {
-    Number(u32),
+    Number(u64),
     Symbol(char),
+    /// A run of digits too large for a `u64`. It's neither a part number
+    /// nor a symbol.
+    Overflow,
 }
 
+/// A number or symbol and the cells of one line it covers.
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vertex {
     pub data: Data,
     pub y: usize,
@@ -27,7 +121,7 @@ pub struct Vertex {
 }
 
 impl Vertex {
-    pub fn number(number: u32, y: usize, min_x: usize) -> Self {
+    pub fn number(number: u64, y: usize, min_x: usize) -> Self {
         let n = format!("{number}");
         Self {
             data: Data::Number(number),
@@ -38,47 +132,73 @@ impl Vertex {
     }
 
     pub fn symbol(symbol: char, y: usize, min_x: usize) -> Self {
+        Self::token(symbol, y, min_x, min_x)
+    }
+
+    /// A symbol covering `min_x..=max_x`, such as a multi-character token,
+    /// named after its first character.
+    pub fn token(symbol: char, y: usize, min_x: usize, max_x: usize) -> Self {
         Self {
             data: Data::Symbol(symbol),
             y,
             min_x,
-            max_x: min_x,
+            max_x,
         }
     }
 
+    pub fn as_number(&self) -> Option<u64> {
+        match self.data {
+            Data::Number(n) => Some(n),
+            Data::Symbol(_) | Data::Overflow => None,
+        }
+    }
+
+    pub fn as_symbol(&self) -> Option<char> {
+        match self.data {
+            Data::Number(_) | Data::Overflow => None,
+            Data::Symbol(s) => Some(s),
+        }
+    }
+
+    /// The columns this vertex covers.
+    pub fn span(&self) -> RangeInclusive<usize> {
+        self.min_x..=self.max_x
+    }
+
+    /// The cells this vertex covers and every cell around them.
     pub fn adjacent_points(&self) -> HashSet<Point> {
-        let mut adjacents: HashSet<Point> = HashSet::new();
-        let min_y = if self.y.checked_sub(1).is_some() { self.y - 1 } else { self.y };
-        let max_y = self.y + 1;
-        let min_x = if self.min_x.checked_sub(1).is_some() {
-            self.min_x - 1
-        } else {
-            self.min_x
-        };
-        let max_x = self.max_x + 1;
-        (min_x..=max_x)
-            .for_each(|x| {
-                (min_y..=max_y).for_each(|y| {
-                    adjacents.insert(Point::new(x, y));
-                })
-            });
+        self.points_around(None)
+    }
 
-        adjacents
+    /// Like `adjacent_points`, but on a `width` by `height` torus, where the
+    /// cells past one edge are those along the opposite edge.
+    pub fn adjacent_points_wrapping(&self, width: usize, height: usize) -> HashSet<Point> {
+        self.points_around(Some((width, height)))
     }
 
+    fn points_around(&self, wrap: Option<(usize, usize)>) -> HashSet<Point> {
+        let (y, min_x, max_x) = (self.y as isize, self.min_x as isize, self.max_x as isize);
+        (y - 1..=y + 1)
+            .flat_map(|y| (min_x - 1..=max_x + 1).map(move |x| (x, y)))
+            .filter_map(|(x, y)| SignedPoint::new(x, y).wrapped(wrap))
+            .collect()
+    }
+
+    /// The cells this vertex covers.
     pub fn occupied_points(&self) -> HashSet<Point> {
         let mut points: HashSet<Point> = HashSet::new();
-        (self.min_x..=self.max_x)
+        self.span()
             .for_each(|x| {
                 points.insert(Point::new(x, self.y));
             });
         points
     }
 
+    /// Whether `other` occupies any of this vertex's adjacent points.
     pub fn is_adjacent_to(&self, other: &Vertex) -> bool {
-        let my_ap = self.adjacent_points();
-        let other_ap = other.occupied_points();
-        !my_ap.is_disjoint(&other_ap)
+        self.y.abs_diff(other.y) <= 1
+            && other.min_x <= self.max_x + 1
+            && self.min_x <= other.max_x + 1
     }
 }
 
@@ -99,6 +219,47 @@ mod tests {
         let number = Vertex::number(12, 0, 0);
         let adj_pts = number.adjacent_points();
         assert_eq!(adj_pts.len(), 6);
+
+        let wrapped = number.adjacent_points_wrapping(5, 3);
+        assert_eq!(wrapped.len(), 12);
+        assert!(wrapped.contains(&Point::new(4, 2)));
+        assert_eq!(number.adjacent_points_wrapping(2, 1).len(), 2);
+        assert!(number.adjacent_points_wrapping(0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_point_neighbors() {
+        let neighbors: Vec<Point> = Point::new(1, 1).neighbors8().collect();
+        assert_eq!(neighbors.len(), 8);
+        assert_eq!(neighbors[0], Point::new(0, 0));
+        assert_eq!(neighbors[7], Point::new(2, 2));
+        assert!(!neighbors.contains(&Point::new(1, 1)));
+        assert_eq!(Point::new(0, 0).neighbors8().count(), 3);
+
+        let orthogonal: Vec<Point> = Point::new(1, 1).neighbors4().collect();
+        assert_eq!(orthogonal, vec![Point::new(1, 0), Point::new(0, 1), Point::new(2, 1), Point::new(1, 2)]);
+        assert_eq!(Point::new(0, 3).neighbors4().count(), 3);
+    }
+
+    #[test]
+    fn test_signed_points() {
+        let corner = SignedPoint::from(Point::new(0, 0));
+        assert_eq!(corner.offset(-1, 2), SignedPoint::new(-1, 2));
+        assert_eq!(corner.offset(-1, 2).to_point(), None);
+        assert_eq!(corner.offset(1, 2).to_point(), Some(Point::new(1, 2)));
+        assert_eq!(corner.offset(-1, -1).wrap(5, 3), Some(Point::new(4, 2)));
+        assert_eq!(SignedPoint::new(-6, 7).wrap(5, 3), Some(Point::new(4, 1)));
+        assert_eq!(corner.wrap(0, 0), None);
+        assert_eq!(corner.wrap(5, 0), None);
+    }
+
+    #[test]
+    fn test_span() {
+        let number = Vertex::number(112, 1, 1);
+        assert_eq!(number.span(), 1..=3);
+        assert_eq!(Vertex::symbol('#', 0, 4).span(), 4..=4);
+        let cells: Vec<usize> = number.span().collect();
+        assert_eq!(cells.len(), number.occupied_points().len());
     }
 
     #[test]
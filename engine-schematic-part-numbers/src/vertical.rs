@@ -0,0 +1,95 @@
+//! Numbers read down the columns, for crossword-style schematics. A digit
+//! can be part of a number across and a number down at once, so these are
+//! kept apart from the schematic's vertices, which are only ever read along
+//! rows.
+
+use crate::{cell_range, Point, Schematic, SymbolRef};
+
+/// A run of at least two digits stacked in one column.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VerticalNumber {
+    pub value: u64,
+    pub x: usize,
+    pub min_y: usize,
+    pub max_y: usize,
+    /// The symbols it touches, in reading order.
+    pub adjacent_symbols: Vec<SymbolRef>,
+}
+
+impl Schematic {
+    /// The numbers read top to bottom down each column, left to right then
+    /// top to bottom. Runs too large for a `u64` are left out.
+    pub fn vertical_numbers(&self) -> Vec<VerticalNumber> {
+        let alphabet = &self.options.alphabet;
+        let digit = |x: usize, y: usize| {
+            let line = self.lines.get(y)?;
+            let mut cell = line[cell_range(line, x)?].chars();
+            let c = cell.next()?;
+            (cell.next().is_none() && alphabet.is_digit(c) && !alphabet.is_blank(c)).then_some(c)
+        };
+        let mut numbers = vec![];
+        for x in 0..self.grid.width() {
+            let mut y = 0;
+            while y < self.grid.height() {
+                let min_y = y;
+                let mut digits = String::new();
+                while let Some(c) = digit(x, y) {
+                    digits.push(c);
+                    y += 1;
+                }
+                if digits.chars().count() >= 2 {
+                    if let Ok(value) = u64::from_str_radix(&digits, alphabet.radix()) {
+                        let max_y = y - 1;
+                        let adjacent_symbols = self.symbols_around(x, min_y, max_y);
+                        numbers.push(VerticalNumber { value, x, min_y, max_y, adjacent_symbols });
+                    }
+                }
+                y = y.max(min_y + 1);
+            }
+        }
+        numbers
+    }
+
+    /// The vertical numbers touching at least one symbol.
+    pub fn vertical_part_numbers(&self) -> Vec<u64> {
+        self.vertical_numbers()
+            .into_iter()
+            .filter(|number| !number.adjacent_symbols.is_empty())
+            .map(|number| number.value)
+            .collect()
+    }
+
+    fn symbols_around(&self, x: usize, min_y: usize, max_y: usize) -> Vec<SymbolRef> {
+        let mut symbols: Vec<SymbolRef> = (min_y..=max_y)
+            .flat_map(|y| self.adjacent_to_point(x, y))
+            .filter_map(|vertex| Some(SymbolRef { symbol: vertex.as_symbol()?, position: Point::new(vertex.min_x, vertex.y) }))
+            .collect();
+        symbols.sort_by_key(|symbol| (symbol.position.y, symbol.position.x));
+        symbols.dedup();
+        symbols
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vertical_numbers() {
+        let schematic = Schematic::parse("1.4.\n2..*\n3.5.\n..6.");
+        let numbers = schematic.vertical_numbers();
+        assert_eq!(numbers.iter().map(|number| number.value).collect::<Vec<_>>(), vec![123, 56]);
+        assert_eq!((numbers[0].x, numbers[0].min_y, numbers[0].max_y), (0, 0, 2));
+        assert!(numbers[0].adjacent_symbols.is_empty());
+        assert_eq!(numbers[1].adjacent_symbols, vec![SymbolRef { symbol: '*', position: Point::new(3, 1) }]);
+        assert_eq!(schematic.vertical_part_numbers(), vec![56]);
+
+        // Across numbers are read as usual alongside.
+        let crossword = Schematic::parse("123\n4.*\n5..");
+        assert_eq!(crossword.part_numbers(), vec![123]);
+        let down: Vec<u64> = crossword.vertical_numbers().iter().map(|number| number.value).collect();
+        assert_eq!(down, vec![145]);
+        assert!(Schematic::parse("1.2\n...").vertical_numbers().is_empty());
+    }
+}
@@ -0,0 +1,43 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use scratchcards::count_copies;
+use std::fmt::Write;
+use std::hint::black_box;
+
+/// `count` cards of ten winning numbers under 51 and twenty-five of mine
+/// over 50, drawn with a fixed linear congruential generator so every run
+/// sees the same input. Half the cards swap one of theirs for one of the
+/// winning numbers, so no card matches more than one and the copy counts
+/// stay small over a million cards.
+fn synthetic(count: usize) -> String {
+    let mut state: u64 = 2023;
+    let mut next = move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 33) as usize
+    };
+    let mut input = String::with_capacity(count * 128);
+    for id in 1..=count {
+        let winning: Vec<usize> = (0..10).map(|n| n * 5 + 1 + next() % 5).collect();
+        let mine = (1..25).map(|n| 51 + n * 2);
+        let first = if next() % 2 == 0 { winning[next() % 10] } else { 51 };
+        write!(input, "Card {id}:").unwrap();
+        winning.iter().for_each(|n| write!(input, " {n:2}").unwrap());
+        input.push_str(" |");
+        std::iter::once(first).chain(mine).for_each(|n| write!(input, " {n:2}").unwrap());
+        input.push('\n');
+    }
+    input
+}
+
+fn copies(c: &mut Criterion) {
+    let input = synthetic(1_000_000);
+    let mut group = c.benchmark_group("copies");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.sample_size(10);
+    group.bench_function("count_copies_1m", |b| {
+        b.iter(|| count_copies(black_box(&input).lines().collect()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, copies);
+criterion_main!(benches);
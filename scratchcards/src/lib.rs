@@ -1,94 +1,259 @@
-use std::collections::{HashSet, HashMap};
-use regex::Regex;
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
 
+mod pile;
+mod scoring;
+
+pub use pile::{CopyReport, Pile, PileError};
+pub use scoring::{Doubling, Linear, ScoringRule, Triangular};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Card {
     pub id: usize,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_sorted"))]
     pub winning_numbers: HashSet<usize>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_sorted"))]
     pub my_numbers: HashSet<usize>,
 }
 
+/// Writes a set of numbers smallest first, so the same card always exports
+/// the same way.
+#[cfg(feature = "serde")]
+fn serialize_sorted<S: serde::Serializer>(numbers: &HashSet<usize>, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut sorted: Vec<usize> = numbers.iter().copied().collect();
+    sorted.sort_unstable();
+    serde::Serialize::serialize(&sorted, serializer)
+}
+
 impl Card {
     pub fn points(&self) -> usize {
-        let match_count = self.match_count();
-        if match_count == 0 {
-            0
-        } else {
-            let mut points: usize = 1;
-            for _m in 0..(match_count - 1) {
-                points *= 2;
-            }
-            points
-        }
+        self.score_with(&Doubling)
     }
 
+    /// The points this card is worth under `rule` rather than the puzzle's.
+    pub fn score_with<R: ScoringRule>(&self, rule: &R) -> usize {
+        rule.score(self.match_count())
+    }
+
+    /// How many of my numbers are winning numbers. When every number is
+    /// under 128, as in the puzzle, each side is a bitmask and this is one
+    /// AND; otherwise the two sets are intersected.
     pub fn match_count(&self) -> usize {
-        let intersection = self.winning_numbers
-            .intersection(&self.my_numbers)
-            .collect::<HashSet<&usize>>();
-        intersection.len()
+        match (bitmask(&self.winning_numbers), bitmask(&self.my_numbers)) {
+            (Some(winning), Some(mine)) => (winning & mine).count_ones() as usize,
+            _ => self.winning_numbers.intersection(&self.my_numbers).count(),
+        }
     }
 }
 
+/// `numbers` as the bits of a `u128`, if they're all under 128.
+fn bitmask(numbers: &HashSet<usize>) -> Option<u128> {
+    numbers
+        .iter()
+        .try_fold(0u128, |mask, n| Some(mask | 1u128.checked_shl(u32::try_from(*n).ok()?)?))
+}
+
 impl TryFrom<&str> for Card {
-    type Error = &'static str;
+    type Error = CardParseError;
 
     fn try_from(candidate: &str) -> Result<Self, Self::Error> {
-        let reg = Regex::new(r"Card\s+(?<id>\d+):\s(?<winning_nums>[\d+|\s+]+)\|(?<my_nums>[\d+|\s+]+)$").unwrap();
-        if let Some(caps) = reg.captures(candidate) {
-            let winning_numbers = caps["winning_nums"]
-                .split_ascii_whitespace()
-                .map(|n| n.parse::<usize>().unwrap())
-                .collect::<HashSet<usize>>();
-            let my_numbers = caps["my_nums"]
-                .split_ascii_whitespace()
-                .map(|n| n.parse::<usize>().unwrap())
-                .collect::<HashSet<usize>>();
-            Ok(Card {
-                id: caps["id"].parse().unwrap(),
-                winning_numbers,
-                my_numbers
-            })
-        } else {
-            Err("failed to parse str into Card")
+        let (header, numbers) = candidate.split_once(':').ok_or(CardParseError::MissingHeader)?;
+        let id = header
+            .strip_prefix("Card")
+            .filter(|id| id.starts_with(char::is_whitespace))
+            .ok_or(CardParseError::MissingHeader)?;
+        let (winning, mine) = numbers.split_once('|').ok_or(CardParseError::MissingSeparator)?;
+        Ok(Card {
+            id: parse_number(id.trim())?,
+            winning_numbers: parse_numbers(winning)?,
+            my_numbers: parse_numbers(mine)?,
+        })
+    }
+}
+
+fn parse_number(token: &str) -> Result<usize, CardParseError> {
+    token.parse().map_err(|_| CardParseError::BadNumber { token: token.to_string() })
+}
+
+fn parse_numbers(numbers: &str) -> Result<HashSet<usize>, CardParseError> {
+    numbers.split_ascii_whitespace().map(parse_number).collect()
+}
+
+/// Why a line isn't a card.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CardParseError {
+    /// The line doesn't start with `Card <id>:`.
+    MissingHeader,
+    /// The id or one of the numbers isn't a number, or is too large.
+    BadNumber { token: String },
+    /// There's no `|` between the winning numbers and mine.
+    MissingSeparator,
+}
+
+impl fmt::Display for CardParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CardParseError::MissingHeader => write!(f, "expected \"Card <id>:\" at the start of the line"),
+            CardParseError::BadNumber { token } => write!(f, "bad number {token:?}"),
+            CardParseError::MissingSeparator => write!(f, "expected \"|\" between the two lists of numbers"),
         }
     }
 }
 
+impl Error for CardParseError {}
+
+/// A line of input that isn't a card. `line_no` is 1-based.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineError {
+    pub line_no: usize,
+    pub error: CardParseError,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line_no, self.error)
+    }
+}
+
+impl Error for LineError {}
+
+#[derive(Debug)]
+pub enum CardsError {
+    Io(io::Error),
+    Parse(LineError),
+}
+
+impl fmt::Display for CardsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CardsError::Io(err) => write!(f, "could not read cards: {err}"),
+            CardsError::Parse(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for CardsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CardsError::Io(err) => Some(err),
+            CardsError::Parse(err) => Some(err),
+        }
+    }
+}
+
+/// Reads cards from `reader` a line at a time, skipping blank lines. A line
+/// that isn't a card is an error but reading goes on; reading stops after
+/// the first I/O error.
+pub fn cards_from<R: BufRead>(reader: R) -> impl Iterator<Item = Result<Card, CardsError>> {
+    numbered_cards_from(reader).map(|card| card.map(|(_, card)| card))
+}
+
+/// Like `cards_from`, but with each card's 1-based line number.
+fn numbered_cards_from<R: BufRead>(reader: R) -> impl Iterator<Item = Result<(usize, Card), CardsError>> {
+    let mut failed = false;
+    reader
+        .lines()
+        .map_while(move |line| {
+            if failed {
+                return None;
+            }
+            failed = line.is_err();
+            Some(line)
+        })
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|(index, line)| {
+            let line = line.map_err(CardsError::Io)?;
+            let card = Card::try_from(line.as_str()).map_err(|error| LineError { line_no: index + 1, error });
+            Ok((index + 1, card.map_err(CardsError::Parse)?))
+        })
+}
+
+/// The total points of the cards read from `reader`, failing on the first
+/// line that isn't a card.
+pub fn total_points<R: BufRead>(reader: R) -> Result<usize, CardsError> {
+    cards_from(reader).map(|card| Ok(card?.points())).sum()
+}
+
+/// How many cards there are once every card's copies are won, reading them
+/// from `reader` and failing on the first line that isn't a card.
+pub fn total_cards<R: BufRead>(reader: R) -> Result<usize, CardsError> {
+    let mut error = None;
+    let total = copies(cards_from(reader).map_while(|card| card.map_err(|err| error = Some(err)).ok()));
+    match error {
+        Some(err) => Err(err),
+        None => Ok(total),
+    }
+}
+
+/// Parses every line of `input` but blank ones, stopping at the first that
+/// isn't a card.
+fn try_cards(input: Vec<&str>) -> Result<Vec<Card>, LineError> {
+    input
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| Card::try_from(*line).map_err(|error| LineError { line_no: index + 1, error }))
+        .collect()
+}
+
+/// The total points of the cards in `input`, skipping lines that aren't cards.
 pub fn add(input: Vec<&str>) -> usize {
     input
         .iter()
-        .filter_map(|line| {
-            if let Ok(card) = Card::try_from(*line) {
-                Some(card.points())
-            } else {
-                None
-            }
-        })
+        .filter_map(|line| Card::try_from(*line).ok())
+        .map(|card| card.points())
         .sum()
 }
 
+/// Like `add`, but failing on the first line that isn't a card.
+pub fn try_add(input: Vec<&str>) -> Result<usize, LineError> {
+    Ok(try_cards(input)?.iter().map(Card::points).sum())
+}
+
+/// How many cards there are once every card's copies are won, skipping lines
+/// that aren't cards.
 pub fn count_copies(input: Vec<&str>) -> usize {
-    let mut counts_by_card_id: HashMap<usize, usize> = HashMap::new();
-    input
-        .iter()
-        .filter_map(|line| {
-            if let Ok(card) = Card::try_from(*line) { Some(card) } else { None }
-        })
-        .for_each(|card| {
-            let count = counts_by_card_id
-                .entry(card.id)
-                .or_insert(0);
-            *count += 1;
-            (0..*count).for_each(|_i| {
-                (1..=card.match_count()).for_each(|offset| {
-                    let id = card.id + offset;
-                    *counts_by_card_id
-                        .entry(id)
-                        .or_insert(0) += 1;
-                })
-            })
-        });
-    counts_by_card_id.values().sum()
+    copies(input.iter().filter_map(|line| Card::try_from(*line).ok()))
+}
+
+/// Like `count_copies`, but failing on the first line that isn't a card.
+pub fn try_count_copies(input: Vec<&str>) -> Result<usize, LineError> {
+    Ok(copies(try_cards(input)?))
+}
+
+fn copies<C: Borrow<Card>>(cards: impl IntoIterator<Item = C>) -> usize {
+    saturating_usize(copy_counts(cards).fold(0, u64::saturating_add))
+}
+
+/// `n`, or `usize::MAX` if it doesn't fit.
+fn saturating_usize(n: u64) -> usize {
+    usize::try_from(n).unwrap_or(usize::MAX)
+}
+
+/// How many of each card there are once every card's copies are won, in
+/// order. Each card wins one copy of each of the next `match_count` cards
+/// for every copy of it there is, so going forward, a card's count is final
+/// by the time it's reached and can be added to the cards it wins in one
+/// step. Cards are taken to be in order of id with none missing, as in the
+/// puzzle, which also promises no card wins copies past the last. Counts
+/// too large for a `u64` saturate at `u64::MAX`.
+fn copy_counts<C: Borrow<Card>>(cards: impl IntoIterator<Item = C>) -> impl Iterator<Item = u64> {
+    let mut counts: Vec<u64> = vec![];
+    cards.into_iter().enumerate().map(move |(index, card)| {
+        let matches = card.borrow().match_count();
+        if counts.len() < index + matches + 1 {
+            counts.resize(index + matches + 1, 0);
+        }
+        // The original, and every copy won so far.
+        let count = counts[index].saturating_add(1);
+        counts[index + 1..=index + matches].iter_mut().for_each(|won| *won = won.saturating_add(count));
+        count
+    })
 }
 
 #[cfg(test)]
@@ -164,11 +329,82 @@ mod tests {
             my_numbers: HashSet::from([83, 86, 6, 31, 17, 9, 48, 53])
         };
         assert_eq!(card_one_point.points(), 1);
+
+        let card_large_numbers = Card {
+            id: 1,
+            winning_numbers: HashSet::from([127, 128, 1000, 5]),
+            my_numbers: HashSet::from([127, 128, 1000, 6])
+        };
+        assert_eq!(card_large_numbers.match_count(), 3);
+        assert_eq!(card_large_numbers.points(), 4);
+        assert_eq!(Card::try_from("Card 1: 128 5 | 128 6").map(|card| card.match_count()), Ok(1));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_card_serde_round_trip() {
+        let card = Card::try_from("Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53").unwrap();
+        let json = serde_json::to_string(&card).unwrap();
+        assert_eq!(json, r#"{"id":1,"winning_numbers":[17,41,48,83,86],"my_numbers":[6,9,17,31,48,53,83,86]}"#);
+        assert_eq!(serde_json::from_str::<Card>(&json).unwrap(), card);
     }
 
     #[test]
     fn test_card_from_str_is_err() {
         let result = Card::try_from("asdf");
         assert!(result.is_err());
+        assert_eq!(Card::try_from("Cards 1: 1 | 2").err(), Some(CardParseError::MissingHeader));
+        assert_eq!(Card::try_from("Card 1: 1 2 3").err(), Some(CardParseError::MissingSeparator));
+        assert_eq!(
+            Card::try_from("Card 1: 1 x | 2").err(),
+            Some(CardParseError::BadNumber { token: "x".to_string() })
+        );
+        assert_eq!(
+            Card::try_from("Card one: 1 | 2").err(),
+            Some(CardParseError::BadNumber { token: "one".to_string() })
+        );
+    }
+
+    #[test]
+    fn test_streaming_totals() {
+        let sample = include_str!("../input/sample.txt");
+        assert_eq!(total_points(sample.as_bytes()).unwrap(), 13);
+        assert_eq!(total_cards(sample.as_bytes()).unwrap(), 30);
+        assert_eq!(cards_from(sample.as_bytes()).count(), 6);
+
+        let input = "Card 1: 41 | 41\n\nCard 2: 1 2\nCard 3: 1 | 2\n";
+        let results: Vec<_> = cards_from(input.as_bytes()).collect();
+        assert_eq!(results.len(), 3);
+        assert!(matches!(&results[1], Err(CardsError::Parse(LineError { line_no: 3, .. }))));
+        let err = total_cards(input.as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "line 3: expected \"|\" between the two lists of numbers");
+        assert!(total_points(&b"Card 1: 1 | 1\n\xff\n"[..]).is_err_and(|err| matches!(err, CardsError::Io(_))));
+    }
+
+    #[test]
+    fn test_fallible_totals_report_the_line() {
+        let sample = include_str!("../input/sample.txt")
+            .split_terminator('\n')
+            .collect::<Vec<&str>>();
+        assert_eq!(try_add(sample.clone()), Ok(13));
+        assert_eq!(try_count_copies(sample.clone()), Ok(30));
+
+        let mut input = sample.clone();
+        input[2] = "Card 3: 1 21 53 59 44  69 82 63 72 16 21 14  1";
+        let err = try_add(input.clone()).unwrap_err();
+        assert_eq!(err, LineError { line_no: 3, error: CardParseError::MissingSeparator });
+        assert_eq!(err.to_string(), "line 3: expected \"|\" between the two lists of numbers");
+        assert_eq!(try_count_copies(input.clone()).unwrap_err().line_no, 3);
+        assert_eq!(add(input), 13 - 2);
+    }
+
+    #[test]
+    fn test_copies_saturate() {
+        // Each card wins the next two, so the counts grow like Fibonacci
+        // numbers and pass u64::MAX well before the hundredth card.
+        let lines: Vec<String> = (1..=100).map(|id| format!("Card {id}: 1 2 | 1 2")).collect();
+        let input: Vec<&str> = lines.iter().map(String::as_str).collect();
+        assert_eq!(count_copies(input.clone()), saturating_usize(u64::MAX));
+        assert_eq!(try_count_copies(input), Ok(saturating_usize(u64::MAX)));
     }
 }
@@ -0,0 +1,289 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::str::FromStr;
+
+use crate::{copy_counts, numbered_cards_from, saturating_usize, Card, CardsError, LineError, ScoringRule};
+
+/// One card's row of `Pile::copy_report`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CopyReport {
+    pub id: usize,
+    pub matches: usize,
+    pub points: usize,
+    /// How many of it there are once every card's copies are won, the
+    /// original included, or `usize::MAX` if there are more than that.
+    pub copies: usize,
+}
+
+/// Why a pile couldn't be read.
+#[derive(Debug)]
+pub enum PileError {
+    Io(io::Error),
+    /// A line that isn't a card.
+    Line(LineError),
+    /// A card that isn't the next in order, as a pile needs them numbered
+    /// from 1. `line_no` is 1-based.
+    UnexpectedId { line_no: usize, id: usize, expected: usize },
+}
+
+impl fmt::Display for PileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PileError::Io(err) => write!(f, "could not read cards: {err}"),
+            PileError::Line(err) => err.fmt(f),
+            PileError::UnexpectedId { line_no, id, expected } => {
+                write!(f, "line {line_no}: expected card {expected} but found card {id}")
+            },
+        }
+    }
+}
+
+impl Error for PileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PileError::Io(err) => Some(err),
+            PileError::Line(err) => Some(err),
+            PileError::UnexpectedId { .. } => None,
+        }
+    }
+}
+
+impl From<CardsError> for PileError {
+    fn from(err: CardsError) -> Self {
+        match err {
+            CardsError::Io(err) => PileError::Io(err),
+            CardsError::Parse(err) => PileError::Line(err),
+        }
+    }
+}
+
+/// A pile of scratchcards, parsed once so it can be asked about repeatedly.
+/// The cards are in order of id with none missing, as in the puzzle.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Pile {
+    cards: Vec<Card>,
+    /// How many of each card there are once every card's copies are won.
+    copies: Vec<u64>,
+}
+
+impl Pile {
+    /// # Panics
+    ///
+    /// If the cards aren't numbered 1, 2, 3 and so on, in order.
+    pub fn new(cards: Vec<Card>) -> Self {
+        if let Some((index, card)) = cards.iter().enumerate().find(|(index, card)| card.id != index + 1) {
+            panic!("expected card {} but found card {}", index + 1, card.id);
+        }
+        let copies = copy_counts(&cards).collect();
+        Self { cards, copies }
+    }
+
+    /// Reads a pile from `reader`, failing on the first line that isn't a
+    /// card or isn't the next card in order.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, PileError> {
+        let mut cards = vec![];
+        for card in numbered_cards_from(reader) {
+            let (line_no, card) = card?;
+            check_next(&cards, &card, line_no)?;
+            cards.push(card);
+        }
+        Ok(Self::new(cards))
+    }
+
+    /// The sum of every card's points.
+    pub fn total_points(&self) -> usize {
+        self.cards.iter().map(Card::points).sum()
+    }
+
+    /// The sum of every card's points under `rule` rather than the puzzle's.
+    pub fn score_with<R: ScoringRule>(&self, rule: &R) -> usize {
+        self.cards.iter().map(|card| card.score_with(rule)).sum()
+    }
+
+    /// How many cards there are once every card's copies are won, or
+    /// `usize::MAX` if there are more than that.
+    pub fn total_cards(&self) -> usize {
+        saturating_usize(self.copies.iter().copied().fold(0, u64::saturating_add))
+    }
+
+    pub fn card(&self, id: usize) -> Option<&Card> {
+        self.index_of(id).map(|index| &self.cards[index])
+    }
+
+    /// How many of card `id` there are once every card's copies are won,
+    /// the original included, or `usize::MAX` if there are more than that.
+    pub fn copies_of(&self, id: usize) -> Option<usize> {
+        self.index_of(id).map(|index| saturating_usize(self.copies[index]))
+    }
+
+    /// A row per card, in order, flat so it exports as easily to CSV as to
+    /// JSON.
+    pub fn copy_report(&self) -> Vec<CopyReport> {
+        self.cards
+            .iter()
+            .zip(&self.copies)
+            .map(|(card, copies)| CopyReport {
+                id: card.id,
+                matches: card.match_count(),
+                points: card.points(),
+                copies: saturating_usize(*copies),
+            })
+            .collect()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Card> {
+        self.cards.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    fn index_of(&self, id: usize) -> Option<usize> {
+        id.checked_sub(1).filter(|index| *index < self.cards.len())
+    }
+}
+
+/// Whether `card`, read from line `line_no`, can follow `cards` in a pile.
+fn check_next(cards: &[Card], card: &Card, line_no: usize) -> Result<(), PileError> {
+    let expected = cards.len() + 1;
+    if card.id == expected {
+        Ok(())
+    } else {
+        Err(PileError::UnexpectedId { line_no, id: card.id, expected })
+    }
+}
+
+impl FromStr for Pile {
+    type Err = PileError;
+
+    /// Parses every line but blank ones, failing on the first that isn't a
+    /// card or isn't the next card in order.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut cards = vec![];
+        for (index, line) in input.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let line_no = index + 1;
+            let card = Card::try_from(line).map_err(|error| PileError::Line(LineError { line_no, error }))?;
+            check_next(&cards, &card, line_no)?;
+            cards.push(card);
+        }
+        Ok(Self::new(cards))
+    }
+}
+
+impl FromIterator<Card> for Pile {
+    /// # Panics
+    ///
+    /// If the cards aren't numbered 1, 2, 3 and so on, in order.
+    fn from_iter<I: IntoIterator<Item = Card>>(cards: I) -> Self {
+        Self::new(cards.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a Pile {
+    type Item = &'a Card;
+    type IntoIter = std::slice::Iter<'a, Card>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for Pile {
+    type Item = Card;
+    type IntoIter = std::vec::IntoIter<Card>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.cards.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Doubling, Linear, Triangular};
+
+    #[test]
+    fn test_pile() {
+        let sample = include_str!("../input/sample.txt");
+        let pile: Pile = sample.parse().unwrap();
+        assert_eq!(pile.len(), 6);
+        assert_eq!(pile.total_points(), 13);
+        assert_eq!(pile.total_cards(), 30);
+        assert_eq!(pile.card(3).map(Card::match_count), Some(2));
+        assert!(pile.card(7).is_none());
+        let copies: Vec<usize> = (1..=6).filter_map(|id| pile.copies_of(id)).collect();
+        assert_eq!(copies, vec![1, 2, 4, 8, 14, 1]);
+        assert_eq!((&pile).into_iter().map(|card| card.id).collect::<Vec<_>>(), vec![1, 2, 3, 4, 5, 6]);
+
+        assert_eq!(Pile::from_reader(sample.as_bytes()).unwrap(), pile);
+        assert_eq!(pile.clone().into_iter().collect::<Pile>(), pile);
+        let err = "Card 1: 1 | 1\nCard 2: 1".parse::<Pile>().unwrap_err();
+        assert!(matches!(err, PileError::Line(LineError { line_no: 2, .. })));
+        assert!(Pile::default().is_empty());
+        assert!(Pile::default().card(0).is_none());
+
+        let skipped = "Card 1: 1 | 1\n\nCard 3: 1 | 1\n";
+        let err = skipped.parse::<Pile>().unwrap_err();
+        assert!(matches!(err, PileError::UnexpectedId { line_no: 3, id: 3, expected: 2 }));
+        assert_eq!(err.to_string(), "line 3: expected card 2 but found card 3");
+        let err = Pile::from_reader(skipped.as_bytes()).unwrap_err();
+        assert!(matches!(err, PileError::UnexpectedId { line_no: 3, .. }));
+        assert!(matches!(Pile::from_reader(&b"Card 1: 1 | 1\n\xff\n"[..]), Err(PileError::Io(_))));
+
+        let huge: Pile = (1..=100).map(|id| Card::try_from(format!("Card {id}: 1 2 | 1 2").as_str()).unwrap()).collect();
+        assert_eq!(huge.total_cards(), usize::MAX);
+        assert_eq!(huge.copies_of(3), Some(4));
+        assert_eq!(huge.copies_of(100), Some(usize::MAX));
+        assert_eq!(huge.copy_report()[99].copies, usize::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected card 1 but found card 2")]
+    fn test_new_checks_the_ids() {
+        Pile::new(vec![Card::try_from("Card 2: 1 | 1").unwrap()]);
+    }
+
+    #[test]
+    fn test_score_with() {
+        let pile: Pile = include_str!("../input/sample.txt").parse().unwrap();
+        assert_eq!(pile.score_with(&Doubling), pile.total_points());
+        assert_eq!(pile.score_with(&Linear), 4 + 2 + 2 + 1);
+        assert_eq!(pile.score_with(&Triangular), 10 + 3 + 3 + 1);
+        assert_eq!(pile.score_with(&|matches: usize| usize::from(matches > 0)), 4);
+    }
+
+    #[test]
+    fn test_copy_report() {
+        let pile: Pile = include_str!("../input/sample.txt").parse().unwrap();
+        let report = pile.copy_report();
+        assert_eq!(report.len(), 6);
+        assert_eq!(report[1], CopyReport { id: 2, matches: 2, points: 2, copies: 2 });
+        assert_eq!(report.iter().map(|row| row.copies).sum::<usize>(), pile.total_cards());
+        assert_eq!(report.iter().map(|row| row.points).sum::<usize>(), pile.total_points());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_copy_report_serde() {
+        let pile: Pile = include_str!("../input/sample.txt").parse().unwrap();
+        let report = pile.copy_report();
+        let json = serde_json::to_string(&report[0]).unwrap();
+        assert_eq!(json, r#"{"id":1,"matches":4,"points":8,"copies":1}"#);
+        let round_trip: Vec<CopyReport> = serde_json::from_str(&serde_json::to_string(&report).unwrap()).unwrap();
+        assert_eq!(round_trip, report);
+    }
+
+    #[test]
+    fn test_pile_with_input() {
+        let pile: Pile = include_str!("../input/input.txt").parse().unwrap();
+        assert_eq!(pile.total_points(), 19135);
+        assert_eq!(pile.total_cards(), 5704953);
+    }
+}
@@ -0,0 +1,67 @@
+/// How many points a card with a given number of matches is worth.
+/// Implemented by `Doubling`, `Linear` and `Triangular`, and by any
+/// `Fn(usize) -> usize`.
+pub trait ScoringRule {
+    fn score(&self, matches: usize) -> usize;
+}
+
+/// The puzzle's rule: one point for the first match, doubled for each match
+/// after. Saturates at `usize::MAX`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Doubling;
+
+/// A point per match.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Linear;
+
+/// One point for the first match, two for the second, and so on.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Triangular;
+
+impl ScoringRule for Doubling {
+    fn score(&self, matches: usize) -> usize {
+        match matches {
+            0 => 0,
+            _ => u32::try_from(matches - 1)
+                .ok()
+                .and_then(|shift| 1usize.checked_shl(shift))
+                .unwrap_or(usize::MAX),
+        }
+    }
+}
+
+impl ScoringRule for Linear {
+    fn score(&self, matches: usize) -> usize {
+        matches
+    }
+}
+
+impl ScoringRule for Triangular {
+    fn score(&self, matches: usize) -> usize {
+        matches * (matches + 1) / 2
+    }
+}
+
+impl<F> ScoringRule for F
+where
+    F: Fn(usize) -> usize,
+{
+    fn score(&self, matches: usize) -> usize {
+        self(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        let scores = |rule: &dyn ScoringRule| (0..=5).map(|matches| rule.score(matches)).collect::<Vec<_>>();
+        assert_eq!(scores(&Doubling), vec![0, 1, 2, 4, 8, 16]);
+        assert_eq!(scores(&Linear), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(scores(&Triangular), vec![0, 1, 3, 6, 10, 15]);
+        assert_eq!(scores(&|matches: usize| matches * 10), vec![0, 10, 20, 30, 40, 50]);
+        assert_eq!(Doubling.score(100), usize::MAX);
+    }
+}